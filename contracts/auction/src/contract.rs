@@ -1,36 +1,46 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    dynamic_link, to_binary, Addr, BankMsg, Binary, Contract, ContractResult, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response, WasmMsg, Reply, ReplyOn, StdResult, StdError, SubcallResponse, Uint128,
+    to_binary, Addr, BankMsg, Binary, ContractResult, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo, QuerierWrapper, Response, WasmMsg, Reply, ReplyOn, StdResult, StdError, Storage, SubcallResponse, Uint128,
 };
+#[cfg(feature = "dynamic-link")]
+use cosmwasm_std::{dynamic_link, Contract};
 
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{auction, list_resolver, list_resolver_read, Auction, ListingToken};
+use crate::msg::{BidGateMsg, ExecuteMsg, InstantiateMsg, QueryMsg, RelistMsg};
+use crate::state::{
+    auction, auction_read, list_resolver, list_resolver_read, proxy_bid_resolver, proxy_bid_resolver_read,
+    Auction, BidGate, ListingToken, PlatformFee, ProxyBid, Relist,
+};
 
 use nft::InstantiateMsg as NftInstantiateMsg;
+use nft::msg::{QueryMsg as NftQueryMsg, RoyaltyInfoResponse, TransferableResponse};
+#[cfg(not(feature = "dynamic-link"))]
 use nft::{ExecuteMsg::{Approve, Transfer, TransferFrom}};
 
 use std::collections::HashMap;
 
 pub const INSTANTIATE_REPLY_ID: u64 = 1;
+pub const MAX_BPS: u64 = 10_000;
 
+#[cfg(feature = "dynamic-link")]
 #[derive(Contract)]
 struct NftContract {
     address: Addr,
 }
 
+#[cfg(feature = "dynamic-link")]
 #[dynamic_link(NftContract)]
 trait Nft: Contract {
-    fn transfer(&self, sender: String, recipient: String, value: Uint128);
-    fn transfer_from(&self, sender: String, recipient: String, value: Uint128);
-    fn approve(&self, sender: String, recipient: String, value: Uint128);
+    fn transfer(&self, recipient: String, value: String, sale: bool) -> Result<(), String>;
+    fn transfer_from(&self, sender: String, recipient: String, value: String) -> Result<(), String>;
+    fn approve(&self, recipient: String, value: String) -> Result<(), String>;
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
@@ -43,6 +53,20 @@ pub fn instantiate(
                 msg: to_binary(&NftInstantiateMsg {
                     name: v.nft_contract_name,
                     symbol: v.nft_contract_symbol,
+                    minter: Some(info.sender.to_string()),
+                    max_supply: None,
+                    token_id_mode: None,
+                    royalty: None,
+                    transferable: None,
+                    description: None,
+                    image: None,
+                    external_link: None,
+                    base_token_uri: None,
+                    admin: Some(info.sender.to_string()),
+                    placeholder_uri: None,
+                    provenance_hash: None,
+                    snapshots: None,
+                    auction: Some(env.contract.address.to_string()),
                 })?,
                 send: vec![],
                 label: "auction-nft".to_string(),
@@ -52,7 +76,26 @@ pub fn instantiate(
         None => {},
     }
 
-    let config_state = Auction { nft_contract_address: None, limit_block_height: msg.auction_limit_block_height };
+    let platform_fee = match msg.platform_fee {
+        Some(v) => {
+            if v.fee_bps > MAX_BPS || v.keeper_fee_bps > MAX_BPS {
+                return Err(ContractError::InvalidFee {});
+            }
+            Some(PlatformFee {
+                collector: deps.api.addr_validate(&v.collector)?,
+                fee_bps: v.fee_bps,
+                keeper_fee_bps: v.keeper_fee_bps,
+            })
+        },
+        None => None,
+    };
+
+    let config_state = Auction {
+        nft_contract_address: None,
+        limit_block_height: msg.auction_limit_block_height,
+        platform_fee,
+        min_bid_increment: msg.min_bid_increment,
+    };
     auction(deps.storage).save(&config_state)?;
     Ok(res)
 }
@@ -65,25 +108,38 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Listing { nft_contract_address, id, minimum_bid } => {
-            execute_listing(deps, env, info, nft_contract_address, id, minimum_bid)
+        ExecuteMsg::Listing { nft_contract_address, id, minimum_bid, gate, relist } => {
+            execute_listing(deps, env, info, nft_contract_address, id, minimum_bid, ListingOptions { gate, relist })
         },
         ExecuteMsg::Bid { listing_id } => {
             execute_bid(deps, env, info, listing_id)
         },
+        ExecuteMsg::ProxyBid { listing_id } => {
+            execute_proxy_bid(deps, env, info, listing_id)
+        },
         ExecuteMsg::Withdraw { listing_id } => {
             execute_withdraw(deps, env, info, listing_id)
-        }
+        },
+        ExecuteMsg::SettleMany { listing_ids } => {
+            execute_settle_many(deps, env, info, listing_ids)
+        },
     }
 }
 
+/// Optional settings of a new listing, validated by `execute_listing`.
+pub struct ListingOptions {
+    pub gate: Option<BidGateMsg>,
+    pub relist: Option<RelistMsg>,
+}
+
 pub fn execute_listing(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     nft_contract_address: Option<String>,
-    id: Uint128,
-    minimum_bid: Coin
+    id: String,
+    minimum_bid: Coin,
+    options: ListingOptions,
 ) -> Result<Response, ContractError> {
     let contract_address: Addr;
     let auction_config = auction(deps.storage).load()?;
@@ -97,61 +153,53 @@ pub fn execute_listing(
         }
     }
 
+    // soulbound tokens can never reach a buyer. Nft contracts that fail the
+    // query, e.g. because they predate it, have no soulbound tokens and the
+    // escrow still rejects tokens that cannot be moved.
+    let transferable: StdResult<TransferableResponse> = deps.querier.query_wasm_smart(
+        contract_address.to_string(),
+        &NftQueryMsg::Transferable { token_id: id.clone() },
+    );
+    if let Ok(TransferableResponse { transferable: false }) = transferable {
+        return Err(ContractError::NonTransferableToken {});
+    }
+
+    let gate = match options.gate {
+        None => None,
+        Some(BidGateMsg::Nft { contract_address }) => Some(BidGate::Nft {
+            contract_address: deps.api.addr_validate(&contract_address)?,
+        }),
+        Some(BidGateMsg::Allowlist { addresses }) => Some(BidGate::Allowlist {
+            addresses: addresses
+                .iter()
+                .map(|addr| deps.api.addr_validate(addr))
+                .collect::<StdResult<Vec<Addr>>>()?,
+        }),
+    };
+
+    let relist = options.relist.map(|v| Relist { remaining: v.remaining, price_drop: v.price_drop });
+
     let auction_limit = auction_config.limit_block_height;
     let listing_id = (contract_address.to_string().clone() + &id.to_string())[10..].to_string();
 
     let listing_token = ListingToken {
         listing_id: listing_id.clone(),
-        token_id: id,
+        token_id: id.clone(),
         contract_address: contract_address.clone(),
         seller: info.sender.clone(),
         max_bid: minimum_bid,
         max_bidder: env.contract.address.clone(),
         block_limit: env.block.height + auction_limit,
+        gate,
+        relist,
     };
 
     list_resolver(deps.storage).save(listing_id.as_bytes(), &listing_token)?;
 
-    // use dynamic link
-    // let nft_contract = NftContract {address: contract_address.clone()};
-    // nft_contract.approve(
-    //     env.contract.address.to_string(),
-    //     env.contract.address.to_string(),
-    //     id.clone()
-    // );
-    // nft_contract.transfer_from(
-    //     info.sender.to_string().clone(),
-    //     env.contract.address.to_string(),
-    //     id.clone()
-    // );
-    
-
     let mut res = Response::new();
     res.add_attribute("action", "listing");
     res.add_attribute("listing_id", listing_id);
-
-    // use message
-    res.add_message(
-        CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: contract_address.to_string().clone(),
-            send: vec![],
-            msg: to_binary(&Approve {
-            recipient: env.contract.address.to_string(),
-            token_id: id.clone(),
-            })?,
-        })
-    );
-    res.add_message(
-        CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: contract_address.to_string(),
-            send: vec![],
-            msg: to_binary(&TransferFrom {
-                sender: info.sender.to_string(),
-                recipient: String::from(env.contract.address.as_str()),
-                token_id: id,
-            })?,
-        }),
-    );
+    escrow_nft(&mut res, &env, &contract_address, &info.sender, &id)?;
 
     Ok(res)
 }
@@ -164,6 +212,110 @@ pub fn execute_bid(
 ) -> Result<Response, ContractError> {
     let key = listing_id.as_bytes();
     let mut listing = list_resolver_read(deps.storage).load(key)?;
+    let send_fund = validate_bid(deps.as_ref(), &env, &info, &listing)?;
+    let increment = bid_increment(&auction_read(deps.storage).load()?);
+
+    let mut res = Response::new();
+    res.add_attribute("action", "bid");
+    res.add_attribute("listing_id", listing_id.clone());
+
+    match proxy_bid_resolver_read(deps.storage).may_load(key)? {
+        Some(proxy) if proxy.max_bid.amount >= send_fund.amount => {
+            // the leading proxy bid still covers this bid, so it is raised and the bid refunded
+            listing.max_bid.amount = Uint128(proxy.max_bid.amount.u128().min(send_fund.amount.u128() + increment));
+            res.add_attribute("status", "outbid");
+            add_payment(&mut res, &info.sender, &send_fund.denom, send_fund.amount);
+        },
+        Some(proxy) => {
+            proxy_bid_resolver(deps.storage).remove(key);
+            add_payment(&mut res, &proxy.bidder, &proxy.max_bid.denom, proxy.max_bid.amount);
+            listing.max_bidder = info.sender.clone();
+            listing.max_bid = send_fund;
+        },
+        None => {
+            if env.contract.address != listing.max_bidder {
+                add_payment(&mut res, &listing.max_bidder, &listing.max_bid.denom, listing.max_bid.amount);
+            }
+            listing.max_bidder = info.sender.clone();
+            listing.max_bid = send_fund;
+        },
+    }
+
+    list_resolver(deps.storage).save(key, &listing)?;
+    Ok(res)
+}
+
+pub fn execute_proxy_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_id: String,
+) -> Result<Response, ContractError> {
+    let key = listing_id.as_bytes();
+    let mut listing = list_resolver_read(deps.storage).load(key)?;
+
+    // the leading proxy bidder raises its maximum instead of bidding against itself
+    if let Some(mut proxy) = proxy_bid_resolver_read(deps.storage).may_load(key)?.filter(|v| v.bidder == info.sender) {
+        if listing.block_limit < env.block.height {
+            return Err(ContractError::AuctionEnded {});
+        }
+        if info.funds.len() != 1 || info.funds[0].denom != proxy.max_bid.denom || info.funds[0].amount.is_zero() {
+            return Err(ContractError::InvalidBid {});
+        }
+        proxy.max_bid.amount = Uint128(proxy.max_bid.amount.u128() + info.funds[0].amount.u128());
+        proxy_bid_resolver(deps.storage).save(key, &proxy)?;
+
+        let mut res = Response::new();
+        res.add_attribute("action", "proxy_bid");
+        res.add_attribute("listing_id", listing_id.clone());
+        res.add_attribute("status", "raised");
+        return Ok(res);
+    }
+
+    let send_fund = validate_bid(deps.as_ref(), &env, &info, &listing)?;
+    if listing.max_bidder == info.sender {
+        return Err(ContractError::AlreadyHighestBidder {});
+    }
+    let increment = bid_increment(&auction_read(deps.storage).load()?);
+    let new_proxy = ProxyBid { bidder: info.sender.clone(), max_bid: send_fund.clone() };
+
+    let mut res = Response::new();
+    res.add_attribute("action", "proxy_bid");
+    res.add_attribute("listing_id", listing_id.clone());
+
+    match proxy_bid_resolver_read(deps.storage).may_load(key)? {
+        Some(proxy) if proxy.max_bid.amount >= send_fund.amount => {
+            // ties go to the earlier proxy bid
+            listing.max_bid.amount = Uint128(proxy.max_bid.amount.u128().min(send_fund.amount.u128() + increment));
+            res.add_attribute("status", "outbid");
+            add_payment(&mut res, &info.sender, &send_fund.denom, send_fund.amount);
+        },
+        Some(proxy) => {
+            add_payment(&mut res, &proxy.bidder, &proxy.max_bid.denom, proxy.max_bid.amount);
+            listing.max_bidder = info.sender.clone();
+            listing.max_bid.amount = Uint128(send_fund.amount.u128().min(proxy.max_bid.amount.u128() + increment));
+            proxy_bid_resolver(deps.storage).save(key, &new_proxy)?;
+        },
+        None => {
+            if env.contract.address != listing.max_bidder {
+                add_payment(&mut res, &listing.max_bidder, &listing.max_bid.denom, listing.max_bid.amount);
+            }
+            listing.max_bidder = info.sender.clone();
+            listing.max_bid.amount = Uint128(send_fund.amount.u128().min(listing.max_bid.amount.u128() + increment));
+            proxy_bid_resolver(deps.storage).save(key, &new_proxy)?;
+        },
+    }
+
+    list_resolver(deps.storage).save(key, &listing)?;
+    Ok(res)
+}
+
+fn validate_bid(
+    deps: Deps,
+    env: &Env,
+    info: &MessageInfo,
+    listing: &ListingToken,
+) -> Result<Coin, ContractError> {
     if listing.block_limit < env.block.height {
         return Err(ContractError::AuctionEnded {});
     }
@@ -172,89 +324,224 @@ pub fn execute_bid(
         return Err(ContractError::InvalidBid {});
     }
 
+    match &listing.gate {
+        Some(BidGate::Nft { contract_address }) => {
+            let balance: u64 = deps.querier.query_wasm_smart(
+                contract_address.to_string(),
+                &NftQueryMsg::Balance { address: info.sender.to_string() },
+            )?;
+            if balance == 0 {
+                return Err(ContractError::BidderNotHolder {});
+            }
+        },
+        Some(BidGate::Allowlist { addresses }) => {
+            if !addresses.contains(&info.sender) {
+                return Err(ContractError::BidderNotAllowed {});
+            }
+        },
+        None => {},
+    }
+
     let send_fund = info.funds[0].clone();
     if send_fund.amount <= listing.max_bid.amount || send_fund.denom != listing.max_bid.denom {
         return Err(ContractError::InvalidBid {});
     }
-    let last_bid = listing.max_bid;
-    let last_bidder = listing.max_bidder;
-
-    listing.max_bidder = info.sender.clone();
-    listing.max_bid = send_fund;
-    list_resolver(deps.storage).save(key, &listing)?;
+    Ok(send_fund)
+}
 
-    if env.contract.address != last_bidder {
-        let mut res = Response::new();
-        res.add_attribute("action", "bid");
-        res.add_attribute("listing_id", listing_id);
-        res.add_message(
-            CosmosMsg::Bank(BankMsg::Send {
-                to_address: last_bidder.to_string(),
-                amount: vec![last_bid],
-            })
-        );
-        Ok(res)
-    } else {
-        let mut res = Response::new();
-        res.add_attribute("action", "bid");
-        res.add_attribute("listing_id", listing_id);
-        Ok(res)
-    }
+fn bid_increment(config: &Auction) -> u128 {
+    config.min_bid_increment.map_or(1, |v| v.u128().max(1))
 }
 
 pub fn execute_withdraw(
     deps: DepsMut,
     env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     listing_id: String,
 ) -> Result<Response, ContractError> {
-    let key = listing_id.as_bytes();
-    let listing = list_resolver_read(deps.storage).load(key)?;
+    let listing = list_resolver_read(deps.storage).load(listing_id.as_bytes())?;
 
     if listing.block_limit >= env.block.height {
         return Err(ContractError::AuctionNotEnded {});
     }
-    list_resolver(deps.storage).remove(key);
 
-    if env.contract.address != listing.max_bidder {
-        let mut res = Response::new();
-        res.add_attribute("action", "withdraw");
-        res.add_attribute("status", "sold");
-        res.add_attribute("listing_id", listing_id);
-        res.add_message(
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: listing.contract_address.to_string(),
-                send: vec![],
-                msg: to_binary(&Transfer {
-                    recipient: listing.max_bidder.to_string(),
-                    token_id: listing.token_id,
-                })?,
-            })            
-        );
-        res.add_message(
-            CosmosMsg::Bank(BankMsg::Send {
-                to_address: listing.max_bidder.to_string(),
-                amount: vec![listing.max_bid],
-            })            
-        );
-        Ok(res)
-    } else {
-        let mut res = Response::new();
-        res.add_attribute("action", "withdraw");
+    let mut res = Response::new();
+    res.add_attribute("action", "withdraw");
+    settle_listing(deps.storage, &deps.querier, &env, &info.sender, listing, &mut res)?;
+    Ok(res)
+}
+
+pub fn execute_settle_many(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_ids: Vec<String>,
+) -> Result<Response, ContractError> {
+    let mut res = Response::new();
+    res.add_attribute("action", "settle_many");
+
+    for listing_id in listing_ids {
+        // skip listings that were already settled or are still running
+        let listing = match list_resolver_read(deps.storage).may_load(listing_id.as_bytes())? {
+            Some(v) => v,
+            None => continue,
+        };
+        if listing.block_limit >= env.block.height {
+            continue;
+        }
+        settle_listing(deps.storage, &deps.querier, &env, &info.sender, listing, &mut res)?;
+    }
+
+    Ok(res)
+}
+
+fn settle_listing(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    env: &Env,
+    keeper: &Addr,
+    mut listing: ListingToken,
+    res: &mut Response,
+) -> Result<(), ContractError> {
+    if env.contract.address == listing.max_bidder {
+        if let Some(relist) = listing.relist.clone().filter(|v| v.remaining > 0) {
+            let drop = relist.price_drop.unwrap_or_else(Uint128::zero);
+            listing.max_bid.amount = Uint128(listing.max_bid.amount.u128().saturating_sub(drop.u128()));
+            listing.block_limit = env.block.height + auction(storage).load()?.limit_block_height;
+            listing.relist = Some(Relist { remaining: relist.remaining - 1, ..relist });
+            list_resolver(storage).save(listing.listing_id.as_bytes(), &listing)?;
+
+            res.add_attribute("status", "relisted");
+            res.add_attribute("listing_id", listing.listing_id);
+            return Ok(());
+        }
+
+        list_resolver(storage).remove(listing.listing_id.as_bytes());
         res.add_attribute("status", "unsold");
-        res.add_attribute("listing_id", listing_id);
-        res.add_message(
-            CosmosMsg::Wasm(WasmMsg::Execute {
-                contract_addr: listing.contract_address.to_string(),
-                send: vec![],
-                msg: to_binary(&Transfer {
-                    recipient: listing.seller.to_string(),
-                    token_id: listing.token_id,
-                })?,
-            })            
-        );
-        Ok(res)
+        res.add_attribute("listing_id", listing.listing_id);
+        release_nft(res, env, &listing.contract_address, &listing.seller, &listing.token_id, false)?;
+        return Ok(());
+    }
+
+    list_resolver(storage).remove(listing.listing_id.as_bytes());
+    res.add_attribute("status", "sold");
+    res.add_attribute("listing_id", listing.listing_id.clone());
+    release_nft(res, env, &listing.contract_address, &listing.max_bidder, &listing.token_id, true)?;
+
+    let price = listing.max_bid.amount;
+    let denom = listing.max_bid.denom.as_str();
+    if let Some(proxy) = proxy_bid_resolver_read(storage).may_load(listing.listing_id.as_bytes())? {
+        // the winner only pays the current price, the rest of the proxy deposit goes back
+        proxy_bid_resolver(storage).remove(listing.listing_id.as_bytes());
+        add_payment(res, &proxy.bidder, denom, Uint128(proxy.max_bid.amount.u128() - price.u128()));
+    }
+    let mut seller_proceeds = price;
+    let mut keeper_reward = Uint128::zero();
+    if let Some(fee) = auction(storage).load()?.platform_fee {
+        // the keeper reward is carved out of the platform fee, not the seller proceeds
+        let platform_fee = price.multiply_ratio(fee.fee_bps, MAX_BPS);
+        keeper_reward = platform_fee.multiply_ratio(fee.keeper_fee_bps, MAX_BPS);
+        seller_proceeds = Uint128(price.u128() - platform_fee.u128());
+        add_payment(res, &fee.collector, denom, Uint128(platform_fee.u128() - keeper_reward.u128()));
+        add_payment(res, keeper, denom, keeper_reward);
+    }
+    // the royalty of the nft comes out of the seller proceeds, nft contracts
+    // without the royalty query pay none
+    let royalty: StdResult<RoyaltyInfoResponse> = querier.query_wasm_smart(
+        listing.contract_address.to_string(),
+        &NftQueryMsg::RoyaltyInfo { token_id: listing.token_id.clone(), sale_price: price },
+    );
+    if let Ok(royalty) = royalty {
+        if !royalty.address.is_empty() {
+            let royalty_amount = royalty.royalty_amount.min(seller_proceeds);
+            seller_proceeds = Uint128(seller_proceeds.u128() - royalty_amount.u128());
+            add_payment(res, &Addr::unchecked(royalty.address), denom, royalty_amount);
+            res.add_attribute("royalty", royalty_amount.to_string());
+        }
+    }
+    add_payment(res, &listing.seller, denom, seller_proceeds);
+    res.add_attribute("keeper", keeper.to_string());
+    res.add_attribute("keeper_reward", keeper_reward.to_string());
+
+    Ok(())
+}
+
+/// Moves the listed nft from the seller into the auction's custody.
+#[cfg(not(feature = "dynamic-link"))]
+fn escrow_nft(res: &mut Response, env: &Env, contract_address: &Addr, seller: &Addr, id: &str) -> StdResult<()> {
+    res.add_message(
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_address.to_string(),
+            send: vec![],
+            msg: to_binary(&Approve {
+                recipient: env.contract.address.to_string(),
+                token_id: id.to_string(),
+                expires: None,
+            })?,
+        })
+    );
+    res.add_message(
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_address.to_string(),
+            send: vec![],
+            msg: to_binary(&TransferFrom {
+                sender: seller.to_string(),
+                recipient: env.contract.address.to_string(),
+                token_id: id.to_string(),
+            })?,
+        }),
+    );
+    Ok(())
+}
+
+#[cfg(feature = "dynamic-link")]
+fn escrow_nft(_res: &mut Response, env: &Env, contract_address: &Addr, seller: &Addr, id: &str) -> StdResult<()> {
+    let nft_contract = NftContract { address: contract_address.clone() };
+    nft_contract
+        .approve(env.contract.address.to_string(), id.to_string())
+        .map_err(StdError::generic_err)?;
+    nft_contract
+        .transfer_from(seller.to_string(), env.contract.address.to_string(), id.to_string())
+        .map_err(StdError::generic_err)?;
+    Ok(())
+}
+
+/// Hands an escrowed nft over to `recipient`, `sale` marks it as sold in the nft history.
+#[cfg(not(feature = "dynamic-link"))]
+fn release_nft(res: &mut Response, _env: &Env, contract_address: &Addr, recipient: &Addr, id: &str, sale: bool) -> StdResult<()> {
+    res.add_message(
+        CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_address.to_string(),
+            send: vec![],
+            msg: to_binary(&Transfer {
+                recipient: recipient.to_string(),
+                token_id: id.to_string(),
+                sale: Some(sale),
+            })?,
+        })
+    );
+    Ok(())
+}
+
+#[cfg(feature = "dynamic-link")]
+fn release_nft(_res: &mut Response, _env: &Env, contract_address: &Addr, recipient: &Addr, id: &str, sale: bool) -> StdResult<()> {
+    let nft_contract = NftContract { address: contract_address.clone() };
+    nft_contract
+        .transfer(recipient.to_string(), id.to_string(), sale)
+        .map_err(StdError::generic_err)?;
+    Ok(())
+}
+
+fn add_payment(res: &mut Response, to: &Addr, denom: &str, amount: Uint128) {
+    if amount.is_zero() {
+        return;
     }
+    res.add_message(
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: to.to_string(),
+            amount: vec![Coin { denom: denom.to_string(), amount }],
+        })
+    );
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -14,4 +14,19 @@ pub enum ContractError {
 
     #[error("InvalidBid")]
     InvalidBid {},
+
+    #[error("BidderNotHolder")]
+    BidderNotHolder {},
+
+    #[error("BidderNotAllowed")]
+    BidderNotAllowed {},
+
+    #[error("InvalidFee")]
+    InvalidFee {},
+
+    #[error("AlreadyHighestBidder")]
+    AlreadyHighestBidder {},
+
+    #[error("NonTransferableToken")]
+    NonTransferableToken {},
 }
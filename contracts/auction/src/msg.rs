@@ -6,6 +6,18 @@ use serde::{Deserialize, Serialize};
 pub struct InstantiateMsg {
     pub auction_nft: Option<AuctionNft>,
     pub auction_limit_block_height: u64,
+    pub platform_fee: Option<PlatformFeeMsg>,
+    /// step used by proxy bids when outbidding, defaults to 1
+    pub min_bid_increment: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct PlatformFeeMsg {
+    pub collector: String,
+    /// share of the sale price taken by the platform, in basis points
+    pub fee_bps: u64,
+    /// share of the platform fee paid to whoever settles the listing, in basis points
+    pub keeper_fee_bps: u64,
 }
 
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
@@ -15,20 +27,49 @@ pub struct AuctionNft {
     pub nft_contract_symbol: String,
 }
 
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BidGateMsg {
+    /// only holders of at least one token of this nft contract can bid
+    Nft { contract_address: String },
+    /// only these addresses can bid
+    Allowlist { addresses: Vec<String> },
+}
+
+/// relisting requested by the seller, see `state::Relist`
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct RelistMsg {
+    /// number of automatic relists
+    pub remaining: u32,
+    /// amount taken off the minimum bid on every relist
+    pub price_drop: Option<Uint128>,
+}
+
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     Listing {
         nft_contract_address: Option<String>,
-        id: Uint128,
-        minimum_bid: Coin
+        id: String,
+        minimum_bid: Coin,
+        gate: Option<BidGateMsg>,
+        relist: Option<RelistMsg>,
     },
     Bid {
         listing_id: String,
     },
+    /// sent funds are the maximum the contract may bid on behalf of the sender,
+    /// the leading proxy bidder adds to its maximum by sending more. The maximum
+    /// is not private, anyone can read it from the contract state
+    ProxyBid {
+        listing_id: String,
+    },
     Withdraw {
         listing_id: String,
-    }
+    },
+    SettleMany {
+        listing_ids: Vec<String>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -9,11 +9,21 @@ use cosmwasm_storage::{
 
 pub static AUCTION: &[u8] = b"auction";
 pub static LIST_RESOLVER_KEY: &[u8] = b"listingresolver";
+pub static PROXY_BID_RESOLVER_KEY: &[u8] = b"proxybidresolver";
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Auction {
     pub nft_contract_address: Option<Addr>,
     pub limit_block_height: u64,
+    pub platform_fee: Option<PlatformFee>,
+    pub min_bid_increment: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PlatformFee {
+    pub collector: Addr,
+    pub fee_bps: u64,
+    pub keeper_fee_bps: u64,
 }
 
 pub fn auction(storage: &mut dyn Storage) -> Singleton<Auction> {
@@ -32,14 +42,49 @@ pub fn list_resolver_read(storage: &dyn Storage) -> ReadonlyBucket<ListingToken>
     bucket_read(storage, LIST_RESOLVER_KEY)
 }
 
+pub fn proxy_bid_resolver(storage: &mut dyn Storage) -> Bucket<ProxyBid> {
+    bucket(storage, PROXY_BID_RESOLVER_KEY)
+}
+
+pub fn proxy_bid_resolver_read(storage: &dyn Storage) -> ReadonlyBucket<ProxyBid> {
+    bucket_read(storage, PROXY_BID_RESOLVER_KEY)
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 #[serde(rename_all = "snake_case")]
 pub struct ListingToken {
     pub listing_id: String,
-    pub token_id: Uint128,
+    pub token_id: String,
     pub contract_address: Addr,
     pub seller: Addr,
     pub max_bid: Coin,
     pub max_bidder: Addr,
     pub block_limit: u64,
+    pub gate: Option<BidGate>,
+    pub relist: Option<Relist>,
+}
+
+/// Restarts an unsold listing instead of returning the nft to the seller.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Relist {
+    /// number of automatic relists left
+    pub remaining: u32,
+    /// amount taken off the minimum bid on every relist
+    pub price_drop: Option<Uint128>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum BidGate {
+    Nft { contract_address: Addr },
+    Allowlist { addresses: Vec<Addr> },
+}
+
+/// The deposit of the leading proxy bidder of a listing. `max_bid` is kept out of
+/// `ListingToken` so that the `ListingToken` query only reveals the current price,
+/// but it is not secret: raw contract state queries can read it.
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct ProxyBid {
+    pub bidder: Addr,
+    pub max_bid: Coin,
 }
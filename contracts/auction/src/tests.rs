@@ -0,0 +1,310 @@
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{
+    coin, coins, from_binary, to_binary, Addr, BankMsg, ContractResult, CosmosMsg, Env, OwnedDeps, Response,
+    SystemResult, Uint128, WasmQuery,
+};
+
+use nft::msg::QueryMsg as NftQueryMsg;
+
+use crate::contract::{execute, instantiate, query_listing_token};
+use crate::state::{proxy_bid_resolver_read, ProxyBid, Relist};
+use crate::msg::{BidGateMsg, ExecuteMsg, InstantiateMsg, PlatformFeeMsg, RelistMsg};
+use crate::ContractError;
+
+const SELLER: &str = "seller";
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+const NFT: &str = "nft_contract";
+const KEEPER: &str = "keeper";
+const COLLECTOR: &str = "collector";
+const GATE_NFT: &str = "gate_nft_contract";
+const DENOM: &str = "ucony";
+
+type MockDeps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+fn setup_contract(platform_fee: Option<PlatformFeeMsg>) -> MockDeps {
+    let mut deps = mock_dependencies(&[]);
+    instantiate(deps.as_mut(), mock_env(), mock_info(SELLER, &[]), InstantiateMsg {
+        auction_nft: None,
+        auction_limit_block_height: 100,
+        platform_fee,
+        min_bid_increment: None,
+    }).unwrap();
+    deps
+}
+
+fn attr_value(res: &Response, key: &str) -> String {
+    res.attributes.iter().find(|attr| attr.key == key).unwrap().value.clone()
+}
+
+fn env_at(height: u64) -> Env {
+    let mut env = mock_env();
+    env.block.height = height;
+    env
+}
+
+/// First height at which listings made by `mock_env` can be settled.
+fn ended() -> u64 {
+    mock_env().block.height + 101
+}
+
+/// Lists token `1` of `NFT` with a minimum bid of 100.
+fn list(deps: &mut MockDeps, gate: Option<BidGateMsg>, relist: Option<RelistMsg>) -> String {
+    list_at(deps, mock_env(), "1", gate, relist)
+}
+
+fn list_at(deps: &mut MockDeps, env: Env, token_id: &str, gate: Option<BidGateMsg>, relist: Option<RelistMsg>) -> String {
+    let res = execute(deps.as_mut(), env, mock_info(SELLER, &[]), ExecuteMsg::Listing {
+        nft_contract_address: Some(NFT.to_string()),
+        id: token_id.to_string(),
+        minimum_bid: coin(100, DENOM),
+        gate,
+        relist,
+    }).unwrap();
+    attr_value(&res, "listing_id")
+}
+
+fn bid(deps: &mut MockDeps, bidder: &str, listing_id: &str, amount: u128) -> Result<Response, ContractError> {
+    execute(deps.as_mut(), mock_env(), mock_info(bidder, &coins(amount, DENOM)), ExecuteMsg::Bid {
+        listing_id: listing_id.to_string(),
+    })
+}
+
+fn proxy_bid(deps: &mut MockDeps, bidder: &str, listing_id: &str, amount: u128) -> Result<Response, ContractError> {
+    execute(deps.as_mut(), mock_env(), mock_info(bidder, &coins(amount, DENOM)), ExecuteMsg::ProxyBid {
+        listing_id: listing_id.to_string(),
+    })
+}
+
+fn proxy(deps: &MockDeps, listing_id: &str) -> Option<ProxyBid> {
+    proxy_bid_resolver_read(&deps.storage).may_load(listing_id.as_bytes()).unwrap()
+}
+
+/// Current price and leader of the listing.
+fn leader(deps: &MockDeps, listing_id: &str) -> (u128, String) {
+    let listing = query_listing_token(deps.as_ref(), listing_id.to_string()).unwrap();
+    (listing.max_bid.amount.u128(), listing.max_bidder.to_string())
+}
+
+fn settle(deps: &mut MockDeps, listing_ids: Vec<&str>) -> Response {
+    execute(deps.as_mut(), env_at(ended()), mock_info(KEEPER, &[]), ExecuteMsg::SettleMany {
+        listing_ids: listing_ids.into_iter().map(|id| id.to_string()).collect(),
+    }).unwrap()
+}
+
+/// Recipients and amounts of the bank sends of `res`, in order.
+fn bank_sends(res: &Response) -> Vec<(String, u128)> {
+    res.messages
+        .iter()
+        .filter_map(|msg| match msg {
+            CosmosMsg::Bank(BankMsg::Send { to_address, amount }) => Some((to_address.clone(), amount[0].amount.u128())),
+            _ => None,
+        })
+        .collect()
+}
+
+fn platform_fee() -> PlatformFeeMsg {
+    PlatformFeeMsg { collector: COLLECTOR.to_string(), fee_bps: 1_000, keeper_fee_bps: 2_500 }
+}
+
+/// Answers `Balance` queries of `GATE_NFT`, where only alice holds a token.
+/// Every other nft query fails like a contract without it.
+fn mock_gate_nft(deps: &mut MockDeps) {
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::Smart { contract_addr, msg } if contract_addr == GATE_NFT => match from_binary(msg) {
+            Ok(NftQueryMsg::Balance { address }) => {
+                let balance: u64 = if address == ALICE { 1 } else { 0 };
+                SystemResult::Ok(ContractResult::Ok(to_binary(&balance).unwrap()))
+            }
+            _ => SystemResult::Ok(ContractResult::Err("unknown query".to_string())),
+        },
+        _ => SystemResult::Ok(ContractResult::Err("unknown query".to_string())),
+    });
+}
+
+#[test]
+fn nft_gate_needs_a_holder() {
+    let mut deps = setup_contract(None);
+    mock_gate_nft(&mut deps);
+    let listing_id = list(&mut deps, Some(BidGateMsg::Nft { contract_address: GATE_NFT.to_string() }), None);
+
+    let err = bid(&mut deps, BOB, &listing_id, 200).unwrap_err();
+    assert_eq!(err, ContractError::BidderNotHolder {});
+    bid(&mut deps, ALICE, &listing_id, 200).unwrap();
+}
+
+#[test]
+fn allowlist_gate_needs_a_listed_bidder() {
+    let mut deps = setup_contract(None);
+    let listing_id = list(&mut deps, Some(BidGateMsg::Allowlist { addresses: vec![ALICE.to_string()] }), None);
+
+    let err = bid(&mut deps, BOB, &listing_id, 200).unwrap_err();
+    assert_eq!(err, ContractError::BidderNotAllowed {});
+    bid(&mut deps, ALICE, &listing_id, 200).unwrap();
+}
+
+#[test]
+fn sold_listings_pay_the_platform_fee_and_the_keeper() {
+    let mut deps = setup_contract(Some(platform_fee()));
+    let listing_id = list(&mut deps, None, None);
+    bid(&mut deps, ALICE, &listing_id, 1_000).unwrap();
+
+    let res = settle(&mut deps, vec![listing_id.as_str()]);
+    assert_eq!(attr_value(&res, "status"), "sold");
+    // 10% platform fee, a quarter of it goes to the keeper
+    assert_eq!(bank_sends(&res), vec![
+        (COLLECTOR.to_string(), 75),
+        (KEEPER.to_string(), 25),
+        (SELLER.to_string(), 900),
+    ]);
+    assert_eq!(attr_value(&res, "keeper_reward"), "25");
+}
+
+#[test]
+fn sold_listings_without_platform_fee_pay_the_seller() {
+    let mut deps = setup_contract(None);
+    let listing_id = list(&mut deps, None, None);
+    bid(&mut deps, ALICE, &listing_id, 1_000).unwrap();
+
+    let res = settle(&mut deps, vec![listing_id.as_str()]);
+    assert_eq!(bank_sends(&res), vec![(SELLER.to_string(), 1_000)]);
+    assert_eq!(attr_value(&res, "keeper_reward"), "0");
+}
+
+#[test]
+fn unsold_listings_pay_nothing() {
+    let mut deps = setup_contract(Some(platform_fee()));
+    let listing_id = list(&mut deps, None, None);
+
+    let res = settle(&mut deps, vec![listing_id.as_str()]);
+    assert_eq!(attr_value(&res, "status"), "unsold");
+    assert_eq!(bank_sends(&res), vec![]);
+    // only the nft goes back to the seller
+    assert_eq!(res.messages.len(), 1);
+    assert!(query_listing_token(deps.as_ref(), listing_id).is_err());
+}
+
+#[test]
+fn settle_many_skips_unknown_and_running_listings() {
+    let mut deps = setup_contract(Some(platform_fee()));
+    let ended_id = list(&mut deps, None, None);
+    let running_id = list_at(&mut deps, env_at(mock_env().block.height + 50), "2", None, None);
+    bid(&mut deps, ALICE, &ended_id, 1_000).unwrap();
+
+    let res = settle(&mut deps, vec![ended_id.as_str(), "unknown", running_id.as_str()]);
+    assert_eq!(bank_sends(&res), vec![
+        (COLLECTOR.to_string(), 75),
+        (KEEPER.to_string(), 25),
+        (SELLER.to_string(), 900),
+    ]);
+    assert!(query_listing_token(deps.as_ref(), ended_id).is_err());
+    assert!(query_listing_token(deps.as_ref(), running_id).is_ok());
+}
+
+#[test]
+fn unsold_listings_relist_with_a_price_drop() {
+    let mut deps = setup_contract(None);
+    let listing_id = list(&mut deps, None, Some(RelistMsg { remaining: 1, price_drop: Some(Uint128(30)) }));
+
+    let res = settle(&mut deps, vec![listing_id.as_str()]);
+    assert_eq!(attr_value(&res, "status"), "relisted");
+    assert!(res.messages.is_empty());
+    let listing = query_listing_token(deps.as_ref(), listing_id.clone()).unwrap();
+    assert_eq!(listing.max_bid, coin(70, DENOM));
+    assert_eq!(listing.block_limit, ended() + 100);
+    assert_eq!(listing.relist, Some(Relist { remaining: 0, price_drop: Some(Uint128(30)) }));
+
+    // without relists left the nft goes back to the seller
+    let res = execute(deps.as_mut(), env_at(ended() + 101), mock_info(KEEPER, &[]), ExecuteMsg::SettleMany {
+        listing_ids: vec![listing_id.clone()],
+    }).unwrap();
+    assert_eq!(attr_value(&res, "status"), "unsold");
+    assert!(query_listing_token(deps.as_ref(), listing_id).is_err());
+}
+
+#[test]
+fn relist_price_drop_stops_at_zero() {
+    let mut deps = setup_contract(None);
+    let listing_id = list(&mut deps, None, Some(RelistMsg { remaining: 2, price_drop: Some(Uint128(500)) }));
+
+    settle(&mut deps, vec![listing_id.as_str()]);
+    let listing = query_listing_token(deps.as_ref(), listing_id).unwrap();
+    assert_eq!(listing.max_bid, coin(0, DENOM));
+    assert_eq!(listing.relist.unwrap().remaining, 1);
+}
+
+#[test]
+fn regular_bids_against_a_proxy() {
+    let mut deps = setup_contract(None);
+    let listing_id = list(&mut deps, None, None);
+    proxy_bid(&mut deps, BOB, &listing_id, 500).unwrap();
+    assert_eq!(leader(&deps, &listing_id), (101, BOB.to_string()));
+
+    // bids up to the proxy maximum are outbid and refunded
+    let res = bid(&mut deps, ALICE, &listing_id, 300).unwrap();
+    assert_eq!(attr_value(&res, "status"), "outbid");
+    assert_eq!(bank_sends(&res), vec![(ALICE.to_string(), 300)]);
+    assert_eq!(leader(&deps, &listing_id), (301, BOB.to_string()));
+
+    let res = bid(&mut deps, ALICE, &listing_id, 500).unwrap();
+    assert_eq!(bank_sends(&res), vec![(ALICE.to_string(), 500)]);
+    assert_eq!(leader(&deps, &listing_id), (500, BOB.to_string()));
+
+    // a bid above the maximum wins and the whole proxy deposit goes back
+    let res = bid(&mut deps, ALICE, &listing_id, 600).unwrap();
+    assert_eq!(bank_sends(&res), vec![(BOB.to_string(), 500)]);
+    assert_eq!(leader(&deps, &listing_id), (600, ALICE.to_string()));
+    assert_eq!(proxy(&deps, &listing_id), None);
+}
+
+#[test]
+fn proxies_outbid_each_other() {
+    let mut deps = setup_contract(None);
+    let listing_id = list(&mut deps, None, None);
+    proxy_bid(&mut deps, BOB, &listing_id, 500).unwrap();
+
+    let res = proxy_bid(&mut deps, ALICE, &listing_id, 800).unwrap();
+    assert_eq!(bank_sends(&res), vec![(BOB.to_string(), 500)]);
+    assert_eq!(leader(&deps, &listing_id), (501, ALICE.to_string()));
+
+    // ties go to the earlier proxy
+    let res = proxy_bid(&mut deps, BOB, &listing_id, 800).unwrap();
+    assert_eq!(attr_value(&res, "status"), "outbid");
+    assert_eq!(bank_sends(&res), vec![(BOB.to_string(), 800)]);
+    assert_eq!(leader(&deps, &listing_id), (800, ALICE.to_string()));
+    assert_eq!(proxy(&deps, &listing_id), Some(ProxyBid { bidder: Addr::unchecked(ALICE), max_bid: coin(800, DENOM) }));
+}
+
+#[test]
+fn leading_proxy_raises_its_maximum() {
+    let mut deps = setup_contract(None);
+    let listing_id = list(&mut deps, None, None);
+    proxy_bid(&mut deps, BOB, &listing_id, 500).unwrap();
+
+    let res = proxy_bid(&mut deps, BOB, &listing_id, 200).unwrap();
+    assert_eq!(attr_value(&res, "status"), "raised");
+    assert_eq!(bank_sends(&res), vec![]);
+    assert_eq!(proxy(&deps, &listing_id).unwrap().max_bid, coin(700, DENOM));
+    assert_eq!(leader(&deps, &listing_id), (101, BOB.to_string()));
+
+    bid(&mut deps, ALICE, &listing_id, 600).unwrap();
+    assert_eq!(leader(&deps, &listing_id), (601, BOB.to_string()));
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info(BOB, &coins(100, "other")), ExecuteMsg::ProxyBid {
+        listing_id: listing_id.clone(),
+    }).unwrap_err();
+    assert_eq!(err, ContractError::InvalidBid {});
+}
+
+#[test]
+fn settlement_refunds_the_unused_proxy_deposit() {
+    let mut deps = setup_contract(None);
+    let listing_id = list(&mut deps, None, None);
+    proxy_bid(&mut deps, BOB, &listing_id, 500).unwrap();
+    bid(&mut deps, ALICE, &listing_id, 300).unwrap();
+
+    // bob wins at 301 and gets back the rest of the 500 deposit
+    let res = settle(&mut deps, vec![listing_id.as_str()]);
+    assert_eq!(bank_sends(&res), vec![(BOB.to_string(), 199), (SELLER.to_string(), 301)]);
+    assert_eq!(proxy(&deps, &listing_id), None);
+}
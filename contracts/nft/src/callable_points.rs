@@ -0,0 +1,31 @@
+use cosmwasm_std::{callable_point, DepsMut, Env};
+
+use crate::contract::{linked_approve, linked_transfer, linked_transfer_from};
+
+// The acting address is the calling contract as reported by the runtime, which
+// must be the trusted auction of the collection. Errors are returned to the
+// caller as strings.
+
+#[callable_point]
+fn transfer(deps: DepsMut, env: Env, recipient: String, value: String, sale: bool) -> Result<(), String> {
+    let caller = deps.api.get_caller_addr().map_err(|e| e.to_string())?;
+    linked_transfer(deps, env, caller.to_string(), recipient, value, sale)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[callable_point]
+fn transfer_from(deps: DepsMut, env: Env, sender: String, recipient: String, value: String) -> Result<(), String> {
+    let caller = deps.api.get_caller_addr().map_err(|e| e.to_string())?;
+    linked_transfer_from(deps, env, caller.to_string(), sender, recipient, value)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
+
+#[callable_point]
+fn approve(deps: DepsMut, env: Env, recipient: String, value: String) -> Result<(), String> {
+    let caller = deps.api.get_caller_addr().map_err(|e| e.to_string())?;
+    linked_approve(deps, env, caller.to_string(), recipient, value)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
@@ -1,8 +1,29 @@
 pub const CONFIG: &[u8] = b"config";
 pub const OWNER_TOKENS: &[u8] = b"owner_tokens";
+pub const OWNER_TOKEN_INDEX: &[u8] = b"owner_token_index";
+pub const BALANCES: &[u8] = b"balances";
 pub const TOKEN_OWNER: &[u8] = b"token_owner";
 pub const TOKEN_APPROVALS: &[u8] = b"token_approvals";
+pub const TOKEN_SPENDERS: &[u8] = b"token_spenders";
 pub const MINTED_TOKEN_ID: &[u8] = b"minted_token_id";
+pub const NEXT_TOKEN_ID: &[u8] = b"next_token_id";
+pub const MIGRATE_TOKEN_SCAN: &[u8] = b"migrate_token_scan";
 pub const KEY_STATE: &[u8] = b"state";
 pub const TOKEN: &[u8] = b"token";
 pub const OPERATORS: &[u8] = b"operators";
+pub const MINTER: &[u8] = b"minter";
+pub const NUM_TOKENS: &[u8] = b"num_tokens";
+pub const MINTED_COUNT: &[u8] = b"minted_count";
+pub const ROYALTY: &[u8] = b"royalty";
+pub const TOKEN_LOCKS: &[u8] = b"token_locks";
+pub const TOKEN_HISTORY: &[u8] = b"token_history";
+pub const TOKEN_HISTORY_LEN: &[u8] = b"token_history_len";
+pub const OWNER_CHECKPOINTS: &[u8] = b"owner_checkpoints";
+pub const BALANCE_CHECKPOINTS: &[u8] = b"balance_checkpoints";
+pub const TOKEN_USERS: &[u8] = b"token_users";
+pub const PERMIT_NONCES: &[u8] = b"permit_nonces";
+pub const DEFAULT_LIMIT: u32 = 10;
+pub const MAX_LIMIT: u32 = 30;
+pub const MAX_ROYALTY_BPS: u64 = 10_000;
+pub const MAX_BATCH_SIZE: usize = 50;
+pub const DEFAULT_MIGRATE_LIMIT: u32 = 100;
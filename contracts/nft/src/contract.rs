@@ -1,16 +1,24 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    attr, to_binary, to_vec, Binary, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult, Storage, Uint128,
+    attr, from_slice, to_binary, to_vec, Api, Binary, BlockInfo, Deps, DepsMut, Env, MessageInfo, Order, Response,
+    StdError, StdResult, Storage, Uint128,
 };
 
-use cosmwasm_storage::PrefixedStorage;
-use std::ops::Add;
+use ripemd160::Ripemd160;
+use sha2::{Digest, Sha256};
+
+use std::collections::BTreeMap;
 
 use crate::constant::*;
 use crate::errors::ContractError;
-use crate::msg::{InstantiateMsg,ExecuteMsg, QueryMsg};
+use crate::msg::{
+    AllNftInfoResponse, ApprovalResponse, ApprovalsResponse, BalanceAtResponse, ContractInfoResponse,
+    Cw721ReceiveMsg, ExecuteMsg, HistoryResponse, InstantiateMsg, IsApprovedForAllResponse, LockStatusResponse,
+    MigrateMsg, MintMsg, MinterResponse, NftInfoResponse, NumTokensResponse, OperatorsResponse, OwnerAtResponse,
+    OwnerOfResponse, PermitMsg, PermitNonceResponse, PermitPayload, ProvenanceResponse, QueryMsg, RoyaltyInfoResponse,
+    TokensResponse, TransferableResponse, UserOfResponse,
+};
 use crate::store::*;
 use crate::state::*;
 
@@ -18,7 +26,7 @@ use crate::state::*;
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     if !is_valid_name(&msg.name) {
@@ -28,14 +36,49 @@ pub fn instantiate(
         return Err(ContractError::InvalidSymbolFormat {});
     }
 
-    let mut config_store = PrefixedStorage::new(deps.storage, CONFIG);
-    let state = to_vec(&State {
+    // hidden collections get their base token uri from the reveal only
+    if msg.placeholder_uri.is_some() && msg.base_token_uri.is_some() {
+        return Err(ContractError::RevealOnly {});
+    }
+
+    let minter = match msg.minter {
+        Some(v) => deps.api.addr_validate(&v)?.to_string(),
+        None => info.sender.to_string(),
+    };
+    write_minter_store(deps.storage, Minter { minter, max_supply: msg.max_supply })?;
+
+    if let Some(royalty) = msg.royalty {
+        let royalty = validate_royalty(deps.api, royalty)?;
+        write_royalty_store(deps.storage, royalty)?;
+    }
+
+    let admin = match msg.admin {
+        Some(v) => deps.api.addr_validate(&v)?.to_string(),
+        None => info.sender.to_string(),
+    };
+
+    let auction = match msg.auction {
+        Some(v) => Some(deps.api.addr_validate(&v)?.to_string()),
+        None => None,
+    };
+
+    write_state_store(deps.storage, State {
         name: msg.name,
         symbol: msg.symbol,
+        token_id_mode: msg.token_id_mode.unwrap_or_default(),
+        transferable: msg.transferable.unwrap_or(true),
+        description: msg.description,
+        image: msg.image,
+        external_link: msg.external_link,
+        base_token_uri: msg.base_token_uri,
+        admin: Some(admin),
+        placeholder_uri: msg.placeholder_uri,
+        provenance_hash: msg.provenance_hash,
+        revealed: false,
+        snapshots: msg.snapshots.unwrap_or(false),
+        auction,
     })?;
 
-    config_store.set(KEY_STATE, &state);
-
     Ok(Response::default())
 }
 
@@ -50,83 +93,517 @@ pub fn execute(
         ExecuteMsg::Transfer {
             recipient,
             token_id,
-        } => execute_transfer(deps, env, info.sender.to_string(), recipient, token_id),
+            sale,
+        } => execute_transfer(deps, env, info.sender.to_string(), recipient, token_id, sale.unwrap_or(false)),
         ExecuteMsg::TransferFrom {
             sender,
             recipient,
             token_id,
-        } => execute_transfer_from(deps, env, sender, recipient, token_id),
+        } => execute_transfer_from(deps, env, info.sender.to_string(), sender, recipient, token_id),
         ExecuteMsg::Approve {
             recipient,
             token_id,
-        } => execute_approve(deps, env, info.sender.to_string(), recipient, token_id),
-        ExecuteMsg::ApproveForAll { opeartor, approved } => {
-            execute_approve_for_all(deps, env, info.sender.to_string(), opeartor, approved)
+            expires,
+        } => execute_approve(deps, env, info.sender.to_string(), recipient, token_id, expires),
+        ExecuteMsg::ApproveForAll { opeartor, approved, expires } => {
+            execute_approve_for_all(deps, env, info.sender.to_string(), opeartor, approved, expires)
+        }
+        ExecuteMsg::Revoke { spender, token_id } => {
+            execute_revoke(deps, env, info.sender.to_string(), spender, token_id)
+        }
+        ExecuteMsg::RevokeAll { operator } => {
+            execute_revoke_all(deps, env, info.sender.to_string(), operator)
+        }
+        ExecuteMsg::Mint(msg) => execute_mint(deps, env, info.sender.to_string(), msg),
+        ExecuteMsg::BatchMint { tokens } => {
+            execute_batch_mint(deps, env, info.sender.to_string(), tokens)
+        }
+        ExecuteMsg::UpdateMetadata { token_id, extension, freeze_metadata } => {
+            execute_update_metadata(deps, env, info.sender.to_string(), token_id, extension, freeze_metadata)
+        }
+        ExecuteMsg::TransferMinter { minter } => {
+            execute_transfer_minter(deps, env, info.sender.to_string(), minter)
+        }
+        ExecuteMsg::Burn { token_id } => {
+            execute_burn(deps, env, info.sender.to_string(), token_id)
+        }
+        ExecuteMsg::Lock { token_id, reason, unlock_authority } => {
+            execute_lock(deps, env, info.sender.to_string(), token_id, reason, unlock_authority)
+        }
+        ExecuteMsg::Unlock { token_id } => {
+            execute_unlock(deps, env, info.sender.to_string(), token_id)
+        }
+        ExecuteMsg::Permit(msg) => execute_permit(deps, env, msg),
+        ExecuteMsg::SetUser { token_id, user, expires } => {
+            execute_set_user(deps, env, info.sender.to_string(), token_id, user, expires)
+        }
+        ExecuteMsg::Reveal { base_uri } => {
+            execute_reveal(deps, env, info.sender.to_string(), base_uri)
+        }
+        ExecuteMsg::SetAuction { auction } => {
+            execute_set_auction(deps, env, info.sender.to_string(), auction)
+        }
+        ExecuteMsg::UpdateCollectionInfo { description, image, external_link, base_token_uri } => {
+            execute_update_collection_info(
+                deps,
+                env,
+                info.sender.to_string(),
+                description,
+                image,
+                external_link,
+                base_token_uri,
+            )
+        }
+        ExecuteMsg::TransferNft { recipient, token_id } => {
+            execute_transfer_nft(deps, env, info.sender.to_string(), recipient, token_id)
+        }
+        ExecuteMsg::SendNft { contract, token_id, msg } => {
+            execute_send_nft(deps, env, info.sender.to_string(), contract, token_id, msg)
+        }
+        ExecuteMsg::BatchTransfer { recipient, token_ids } => {
+            execute_batch_transfer(deps, env, info.sender.to_string(), recipient, token_ids)
+        }
+        ExecuteMsg::ApproveAll { operator, expires } => {
+            execute_approve_for_all(deps, env, info.sender.to_string(), operator, true, expires)
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(
+    deps: DepsMut,
+    _env: Env,
+    msg: MigrateMsg,
+) -> Result<Response, ContractError> {
+    let limit = msg.limit.unwrap_or(DEFAULT_MIGRATE_LIMIT);
+    let owner_tokens_migrated = migrate_owner_tokens(deps.storage, limit)?;
+    let tokens_scanned = migrate_tokens(deps.storage, limit)?;
+    let approvals_migrated = migrate_token_approvals(deps.storage, limit)?;
+    let operators_migrated = migrate_operators(deps.storage, limit)?;
+    migrate_minter(deps.storage, deps.api, msg.minter, msg.max_supply)?;
+    migrate_admin(deps.storage)?;
+    migrate_minted_count(deps.storage)?;
+
+    let res = Response {
+        submessages: vec![],
+        messages: vec![],
+        attributes: vec![
+            attr("action", "migrate"),
+            attr("owner_tokens_migrated", owner_tokens_migrated),
+            attr("tokens_scanned", tokens_scanned),
+            attr("approvals_migrated", approvals_migrated),
+            attr("operators_migrated", operators_migrated),
+            ],
+        data: None,
+    };
+
+    Ok(res)
+}
+
+/// Moves up to `limit` per-owner `Vec<TokenId>` lists into the (owner, token_id)
+/// index and the balance counters. Owners already migrated have no list left,
+/// so each migration picks up where the previous one stopped. Returns whether
+/// every list has been moved.
+fn migrate_owner_tokens(store: &mut dyn Storage, limit: u32) -> StdResult<bool> {
+    let owner_tokens = owner_tokens_resolver_read(store)
+        .range(None, None, Order::Ascending)
+        .take(limit as usize)
+        .collect::<StdResult<Vec<(Vec<u8>, Vec<TokenId>)>>>()?;
+
+    for (key, token_id_set) in owner_tokens {
+        let owner = String::from_utf8(key.clone()).map_err(StdError::invalid_utf8)?;
+        for token_id in token_id_set {
+            // skip tokens that changed hands between two pages
+            if read_token_owner_store(store, token_id.clone())?.as_deref() != Some(owner.as_str()) {
+                continue;
+            }
+            owner_token_index_resolver(store, &owner).save(&token_id.as_bytes(), &token_id)?;
+        }
+        let balance = owner_token_index_resolver_read(store, &owner)
+            .range(None, None, Order::Ascending)
+            .count() as u64;
+        balances_resolver(store).save(&key, &balance)?;
+        owner_tokens_resolver(store).remove(&key);
+    }
+
+    let done = owner_tokens_resolver_read(store)
+        .range(None, None, Order::Ascending)
+        .next()
+        .is_none();
+
+    Ok(done)
+}
+
+/// Scans the token store `limit` tokens per migration for the next sequential
+/// id and the token count. Returns whether the scan is done.
+fn migrate_tokens(store: &mut dyn Storage, limit: u32) -> StdResult<bool> {
+    let minted_token_ids = minted_token_id_resolver_read(store).may_load(b"minter")?;
+    if minted_token_ids.is_none() && num_tokens_resolver_read(store).may_load()?.is_some() {
+        return Ok(true);
+    }
+
+    let mut scan = match token_scan_resolver_read(store).may_load()? {
+        Some(v) => v,
+        None => TokenScan {
+            start_after: None,
+            last_token_id: minted_token_ids.iter().flatten().filter_map(parse_token_id).max(),
+            count: 0,
+        },
+    };
+    let start = scan.start_after.clone().map(exclusive_start);
+    let tokens = token_resolver_read(store)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit as usize)
+        .collect::<StdResult<Vec<(Vec<u8>, Token)>>>()?;
+
+    for (key, token) in tokens {
+        scan.last_token_id = scan.last_token_id.max(parse_token_id(&token.token_id));
+        scan.count += 1;
+        scan.start_after = Some(key);
+    }
+
+    let start = scan.start_after.clone().map(exclusive_start);
+    let done = token_resolver_read(store)
+        .range(start.as_deref(), None, Order::Ascending)
+        .next()
+        .is_none();
+    if !done {
+        token_scan_resolver(store).save(&scan)?;
+        return Ok(false);
+    }
+
+    token_scan_resolver(store).remove();
+    migrate_token_id_counter(store, &scan)?;
+    migrate_num_tokens(store, &scan)?;
+
+    Ok(true)
+}
+
+/// Replaces the legacy minted token id list with the `next_token_id` counter.
+/// Mint used to overwrite that list with the minter's own tokens, so the
+/// counter starts after the highest id found in the list or the token store.
+fn migrate_token_id_counter(store: &mut dyn Storage, scan: &TokenScan) -> StdResult<()> {
+    if minted_token_id_resolver_read(store).may_load(b"minter")?.is_none() {
+        return Ok(());
+    }
+
+    if let Some(v) = scan.last_token_id {
+        write_next_token_id_store(store, Uint128(v.u128() + 1))?;
+    }
+    minted_token_ids_resolver(store).remove(b"minter");
+
+    Ok(())
+}
+
+/// Counts the existing tokens once, the supply is tracked by mint and burn
+/// afterwards.
+fn migrate_num_tokens(store: &mut dyn Storage, scan: &TokenScan) -> StdResult<()> {
+    if num_tokens_resolver_read(store).may_load()?.is_none() {
+        write_num_tokens_store(store, scan.count)?;
+    }
+
+    Ok(())
+}
+
+fn parse_token_id(token_id: &TokenId) -> Option<Uint128> {
+    token_id.as_string().parse::<u128>().ok().map(Uint128)
+}
+
+/// Moves up to `limit` single approved addresses per token into the
+/// per-spender approvals. Legacy approvals had no expiration, so they never
+/// expire. Returns whether every approval has been moved.
+fn migrate_token_approvals(store: &mut dyn Storage, limit: u32) -> StdResult<bool> {
+    let approvals = token_approvals_resolver_read(store)
+        .range(None, None, Order::Ascending)
+        .take(limit as usize)
+        .collect::<StdResult<Vec<(Vec<u8>, String)>>>()?;
+
+    for (key, spender) in approvals {
+        let token_id: TokenId = from_slice(&key)?;
+        write_token_approval_store(store, token_id, spender, Expiration::Never {})?;
+        token_approvals_resolver(store).remove(&key);
+    }
+
+    let done = token_approvals_resolver_read(store)
+        .range(None, None, Order::Ascending)
+        .next()
+        .is_none();
+
+    Ok(done)
+}
+
+/// Moves up to `limit` operators of the first release to the per-owner
+/// operators. Returns whether every operator has been moved.
+fn migrate_operators(store: &mut dyn Storage, limit: u32) -> StdResult<bool> {
+    for (key, approved) in read_legacy_operators_store(store, limit) {
+        if approved {
+            let (sender, rest) = decode_legacy_string(&key)?;
+            let (operator, _) = decode_legacy_string(rest)?;
+            write_operators_store(store, sender, operator, Expiration::Never {})?;
         }
-        ExecuteMsg::Mint { name, uri } => execute_mint(deps, env, info.sender.to_string(), name, uri),
+        remove_legacy_operator_store(store, &key);
+    }
+
+    Ok(read_legacy_operators_store(store, 1).is_empty())
+}
+
+/// Splits off a bincode encoded string, a little-endian u64 length followed by the bytes.
+fn decode_legacy_string(bytes: &[u8]) -> StdResult<(String, &[u8])> {
+    if bytes.len() < 8 {
+        return Err(StdError::generic_err("invalid legacy operator key"));
+    }
+    let (len, rest) = bytes.split_at(8);
+    let mut len_bytes = [0u8; 8];
+    len_bytes.copy_from_slice(len);
+    let len = u64::from_le_bytes(len_bytes) as usize;
+    if rest.len() < len {
+        return Err(StdError::generic_err("invalid legacy operator key"));
+    }
+
+    let (value, rest) = rest.split_at(len);
+    let value = String::from_utf8(value.to_vec()).map_err(StdError::invalid_utf8)?;
+    Ok((value, rest))
+}
+
+/// Collections instantiated before the minter role could not mint after the
+/// upgrade, so they get the minter of the message or their admin.
+fn migrate_minter(
+    store: &mut dyn Storage,
+    api: &dyn Api,
+    minter: Option<String>,
+    max_supply: Option<u64>,
+) -> Result<(), ContractError> {
+    if read_minter_store(store)?.is_some() {
+        return Ok(());
+    }
+
+    let minter = match (minter, read_state_store(store)?.admin) {
+        (Some(v), _) => api.addr_validate(&v)?.to_string(),
+        (None, Some(admin)) => admin,
+        (None, None) => return Err(ContractError::MinterRequired {}),
+    };
+    write_minter_store(store, Minter { minter, max_supply })?;
+
+    Ok(())
+}
+
+/// Collections instantiated before the admin get their minter as admin.
+fn migrate_admin(store: &mut dyn Storage) -> StdResult<()> {
+    let mut state = read_state_store(store)?;
+    if state.admin.is_some() {
+        return Ok(());
+    }
+
+    state.admin = read_minter_store(store)?.map(|minter| minter.minter);
+    write_state_store(store, state)?;
+
+    Ok(())
+}
+
+/// Collections without burn minted exactly the existing tokens, so the
+/// minted count starts at the token count once it is known.
+fn migrate_minted_count(store: &mut dyn Storage) -> StdResult<()> {
+    if minted_count_resolver_read(store).may_load()?.is_some() {
+        return Ok(());
+    }
+
+    if let Some(num_tokens) = num_tokens_resolver_read(store).may_load()? {
+        write_minted_count_store(store, num_tokens)?;
     }
+
+    Ok(())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(
     deps: Deps,
-    _env: Env,
+    env: Env,
     msg: QueryMsg,
 ) -> StdResult<Binary> {
     match msg {
         QueryMsg::Balance { address } => balance(deps, address),
         QueryMsg::Owner { token_id } => owner(deps, token_id),
-        QueryMsg::Allowance { token_id } => allowance(deps, token_id),
+        QueryMsg::Allowance { token_id } => allowance(deps, env, token_id),
         QueryMsg::Token { token_id } => token(deps, token_id),
+        QueryMsg::Minter {} => minter(deps),
+        QueryMsg::NumTokens {} => num_tokens(deps),
+        QueryMsg::OwnerOf { token_id, include_expired } => {
+            owner_of(deps, env, token_id, include_expired.unwrap_or(false))
+        }
+        QueryMsg::Approval { token_id, spender, include_expired } => {
+            approval(deps, env, token_id, spender, include_expired.unwrap_or(false))
+        }
+        QueryMsg::Approvals { token_id, include_expired } => {
+            approvals(deps, env, token_id, include_expired.unwrap_or(false))
+        }
+        QueryMsg::AllOperators { owner, include_expired, start_after, limit } => {
+            all_operators(deps, env, owner, include_expired.unwrap_or(false), start_after, limit)
+        }
+        QueryMsg::IsApprovedForAll { owner, operator } => {
+            is_approved_for_all(deps, env, owner, operator)
+        }
+        QueryMsg::LockStatus { token_id } => lock_status(deps, token_id),
+        QueryMsg::PermitNonce { owner } => permit_nonce(deps, owner),
+        QueryMsg::UserOf { token_id } => user_of(deps, env, token_id),
+        QueryMsg::OwnerAt { token_id, height } => owner_at(deps, token_id, height),
+        QueryMsg::BalanceAt { address, height } => balance_at(deps, address, height),
+        QueryMsg::History { token_id, start_after, limit } => history(deps, token_id, start_after, limit),
+        QueryMsg::Provenance {} => provenance(deps),
+        QueryMsg::Transferable { token_id } => transferable(deps, token_id),
+        QueryMsg::RoyaltyInfo { token_id, sale_price } => royalty_info(deps, token_id, sale_price),
+        QueryMsg::ContractInfo {} => contract_info(deps),
+        QueryMsg::NftInfo { token_id } => nft_info(deps, token_id),
+        QueryMsg::AllNftInfo { token_id, include_expired } => {
+            all_nft_info(deps, env, token_id, include_expired.unwrap_or(false))
+        }
+        QueryMsg::Tokens { owner, start_after, limit } => tokens(deps, owner, start_after, limit),
+        QueryMsg::AllTokens { start_after, limit } => all_tokens(deps, start_after, limit),
     }
 }
 
+/// Transfer called through the dynamic link by `caller`, which must be the
+/// trusted auction.
+#[cfg(any(feature = "dynamic-link", test))]
+pub(crate) fn linked_transfer(
+    deps: DepsMut,
+    env: Env,
+    caller: String,
+    recipient: String,
+    value: String,
+    sale: bool,
+) -> Result<Response, ContractError> {
+    check_auction(deps.storage, &caller)?;
+    execute_transfer(deps, env, caller, recipient, value, sale)
+}
+
+#[cfg(any(feature = "dynamic-link", test))]
+pub(crate) fn linked_transfer_from(
+    deps: DepsMut,
+    env: Env,
+    caller: String,
+    sender: String,
+    recipient: String,
+    value: String,
+) -> Result<Response, ContractError> {
+    check_auction(deps.storage, &caller)?;
+    execute_transfer_from(deps, env, caller, sender, recipient, value)
+}
+
+#[cfg(any(feature = "dynamic-link", test))]
+pub(crate) fn linked_approve(
+    deps: DepsMut,
+    env: Env,
+    caller: String,
+    recipient: String,
+    value: String,
+) -> Result<Response, ContractError> {
+    check_auction(deps.storage, &caller)?;
+    execute_approve(deps, env, caller, recipient, value, None)
+}
+
 fn execute_transfer(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     sender: String,
     recipient: String,
-    value: Uint128,
+    value: String,
+    sale: bool,
 ) -> Result<Response, ContractError> {
     let token_id = TokenId::new(value);
+    // only the trusted auction records sales, so holders cannot fake them.
+    // Any other caller still transfers, so auctions the collection does not trust can settle
+    let trusted = read_state_store(deps.storage)?.auction.as_deref() == Some(sender.as_str());
+    let transfer_type = if sale && trusted {
+        TransferType::Sale
+    } else {
+        TransferType::Transfer
+    };
 
-    handle_transfer(deps, sender, recipient, token_id)
+    handle_transfer(deps, &env, sender, recipient, token_id, transfer_type)
 }
 
 fn execute_transfer_from(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
+    spender: String,
     sender: String,
     recipient: String,
-    value: Uint128,
+    value: String,
 ) -> Result<Response, ContractError> {
     let token_id = TokenId::new(value);
-    
-    // validation allowance
-    if !validate_allowance(deps.storage, &token_id, recipient.clone()) {
-        return Err(ContractError::NotExistTokenAllowance {});
+
+    if read_token_owner_store(deps.storage, token_id.clone())?.is_none() {
+        return Err(ContractError::NotExistToken {});
     }
 
-    handle_transfer(deps, sender, recipient, token_id)
+    // the caller must be the owner, the approved spender of the token or an operator of the owner
+    if !check_be_able_to_send(deps.storage, &env.block, &token_id, spender)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    handle_transfer(deps, &env, sender, recipient, token_id, TransferType::Transfer)
+}
+
+fn execute_transfer_nft(
+    deps: DepsMut,
+    env: Env,
+    spender: String,
+    recipient: String,
+    value: String,
+) -> Result<Response, ContractError> {
+    let owner = match read_token_owner_store(deps.storage, TokenId::new(value.clone()))? {
+        Some(v) => v,
+        None => return Err(ContractError::NotExistToken {}),
+    };
+
+    execute_transfer_from(deps, env, spender, owner, recipient, value)
+}
+
+fn execute_send_nft(
+    deps: DepsMut,
+    env: Env,
+    spender: String,
+    contract: String,
+    value: String,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    let mut res = execute_transfer_nft(deps, env, spender.clone(), contract.clone(), value.clone())?;
+
+    let receive_msg = Cw721ReceiveMsg {
+        sender: spender,
+        token_id: value,
+        msg,
+    };
+    res.messages.push(receive_msg.into_cosmos_msg(contract)?);
+
+    Ok(res)
 }
 
 fn handle_transfer(
     deps: DepsMut,
+    env: &Env,
     from: String,
     to: String,
     token_id: TokenId,
+    transfer_type: TransferType,
 ) -> Result<Response, ContractError> {
     // validation token
-    if !validate_token_id(deps.storage, &token_id) {
+    if !validate_token_id(deps.storage, &token_id)? {
         return Err(ContractError::NotExistToken {});
     }
 
     // validation token owner
-    let (_, is_owner) = validate_token_owner(deps.storage, &token_id, from.clone());
+    let (_, is_owner) = validate_token_owner(deps.storage, &token_id, from.clone())?;
     if !is_owner {
         return Err(ContractError::InvalidTokenOwner {});
     }
 
+    if read_token_lock_store(deps.storage, token_id.clone())?.is_some() {
+        return Err(ContractError::TokenLocked {});
+    }
+
+    check_transferable(deps.storage, &token_id)?;
+
     /* update owner_tokens_store */
     // for from addr
     update_owner_tokens_store(deps.storage, token_id.clone(), from.clone(), false)?;
@@ -135,6 +612,10 @@ fn handle_transfer(
 
     // update token_owner_store
     write_token_owner_store(deps.storage, token_id.clone(), to.clone())?;
+    add_token_history_store(deps.storage, token_id.clone(), to.clone(), &env.block, transfer_type)?;
+    write_snapshot(deps.storage, &env.block, &[token_id.clone()], &[from.clone(), to.clone()])?;
+
+    clear_token_grants(deps.storage, token_id.clone())?;
 
     let res = Response {
         submessages: vec![],
@@ -150,42 +631,102 @@ fn handle_transfer(
     Ok(res)
 }
 
+fn execute_batch_transfer(
+    deps: DepsMut,
+    env: Env,
+    spender: String,
+    recipient: String,
+    values: Vec<String>,
+) -> Result<Response, ContractError> {
+    if values.is_empty() || values.len() > MAX_BATCH_SIZE {
+        return Err(ContractError::InvalidBatchSize {});
+    }
+
+    // owner index and balances are written once per owner
+    let mut transferred: BTreeMap<String, Vec<TokenId>> = BTreeMap::new();
+    let mut token_ids: Vec<TokenId> = vec![];
+    for value in values {
+        let token_id = TokenId::new(value);
+        if token_ids.contains(&token_id) {
+            return Err(ContractError::DuplicateTokenId {});
+        }
+
+        let owner = match read_token_owner_store(deps.storage, token_id.clone())? {
+            Some(v) => v,
+            None => return Err(ContractError::NotExistToken {}),
+        };
+        if !check_be_able_to_send(deps.storage, &env.block, &token_id, spender.clone())? {
+            return Err(ContractError::Unauthorized {});
+        }
+        if read_token_lock_store(deps.storage, token_id.clone())?.is_some() {
+            return Err(ContractError::TokenLocked {});
+        }
+        check_transferable(deps.storage, &token_id)?;
+
+        write_token_owner_store(deps.storage, token_id.clone(), recipient.clone())?;
+        add_token_history_store(deps.storage, token_id.clone(), recipient.clone(), &env.block, TransferType::Transfer)?;
+        clear_token_grants(deps.storage, token_id.clone())?;
+
+        transferred.entry(owner).or_default().push(token_id.clone());
+        token_ids.push(token_id);
+    }
+    let mut owners: Vec<String> = vec![recipient.clone()];
+    for (owner, owner_token_ids) in transferred {
+        remove_owner_tokens_store(deps.storage, owner.clone(), &owner_token_ids)?;
+        owners.push(owner);
+    }
+    add_owner_tokens_store(deps.storage, recipient.clone(), &token_ids)?;
+    write_snapshot(deps.storage, &env.block, &token_ids, &owners)?;
+
+    let token_ids: Vec<String> = token_ids.iter().map(|token_id| token_id.as_string()).collect();
+    let res = Response {
+        submessages: vec![],
+        messages: vec![],
+        attributes: vec![
+            attr("action", "batch_transfer"),
+            attr("sender", spender),
+            attr("recipient", recipient),
+            attr("token_ids", token_ids.join(",")),
+        ],
+        data: None,
+    };
+    Ok(res)
+}
+
 fn update_owner_tokens_store(
     store: &mut dyn Storage,
     token_id: TokenId,
     owner: String,
     received: bool,
 ) -> StdResult<()> {
-    let mut token_id_set = read_owner_tokens_store(store, owner.clone())?;
     if received {
-        token_id_set.push(token_id.clone());
-        write_owner_tokens_store(store, owner.clone(), token_id_set)?;
+        add_owner_token_store(store, owner, token_id)
     } else {
-        let mut new_token_id_set: Vec<TokenId> = Vec::new();
-        for elm in token_id_set.into_iter() {
-            if token_id.equal(&elm) {
-                continue;
-            }
-            new_token_id_set.push(elm);
-        }
-        write_owner_tokens_store(store, owner, new_token_id_set)?;
+        remove_owner_token_store(store, owner, token_id)
     }
-    Ok(())
 }
 
 fn execute_approve(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     sender: String,
     recipient: String,
-    value: Uint128,
+    value: String,
+    expires: Option<Expiration>,
 ) -> Result<Response, ContractError> {
     let token_id = TokenId::new(value);
-    if !check_be_able_to_approve(deps.storage, &token_id, sender.clone()) {
+    if !check_be_able_to_approve(deps.storage, &env.block, &token_id, sender.clone())? {
         return Err(ContractError::CanNotApprove{});
     }
 
-    write_token_approvals_store(deps.storage, token_id.clone(), recipient.clone())?;
+    check_transferable(deps.storage, &token_id)?;
+
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::Expired{});
+    }
+
+    write_token_approval_store(deps.storage, token_id.clone(), recipient.clone(), expires)?;
 
     let res = Response {
         submessages: vec![],
@@ -202,17 +743,93 @@ fn execute_approve(
     Ok(res)
 }
 
+fn execute_permit(
+    deps: DepsMut,
+    env: Env,
+    msg: PermitMsg,
+) -> Result<Response, ContractError> {
+    let owner = match read_token_owner_store(deps.storage, TokenId::new(msg.token_id.clone()))? {
+        Some(v) => v,
+        None => return Err(ContractError::NotExistToken {}),
+    };
+
+    if msg.nonce != read_permit_nonce_store(deps.storage, owner.clone())? {
+        return Err(ContractError::InvalidNonce {});
+    }
+
+    let payload = PermitPayload {
+        spender: deps.api.addr_validate(&msg.spender)?.to_string(),
+        token_id: msg.token_id.clone(),
+        nonce: msg.nonce,
+        expires: msg.expires.clone(),
+        chain_id: env.block.chain_id.clone(),
+        contract_address: env.contract.address.to_string(),
+    };
+    let hash = Sha256::digest(&to_vec(&payload)?);
+    let signer = permit_signer(deps.api, hash.as_slice(), msg.signature.as_slice(), msg.pubkey.as_slice())?;
+
+    // the signing key must belong to the owner
+    if deps.api.addr_canonicalize(&owner)?.as_slice() != signer.as_slice() {
+        return Err(ContractError::InvalidSignature {});
+    }
+
+    apply_permit(deps, env, owner, msg)
+}
+
+/// Verifies `signature` of `hash` and returns the canonical address of `pubkey`,
+/// the ripemd160 hash of its sha256 hash.
+pub(crate) fn permit_signer(
+    api: &dyn Api,
+    hash: &[u8],
+    signature: &[u8],
+    pubkey: &[u8],
+) -> Result<Vec<u8>, ContractError> {
+    let verified = api
+        .secp256k1_verify(hash, signature, pubkey)
+        .map_err(|_| ContractError::InvalidSignature {})?;
+    if !verified {
+        return Err(ContractError::InvalidSignature {});
+    }
+
+    Ok(Ripemd160::digest(&Sha256::digest(pubkey)).to_vec())
+}
+
+/// Uses up the nonce of `owner` and approves the spender of a verified permit.
+pub(crate) fn apply_permit(
+    deps: DepsMut,
+    env: Env,
+    owner: String,
+    msg: PermitMsg,
+) -> Result<Response, ContractError> {
+    write_permit_nonce_store(deps.storage, owner.clone(), msg.nonce + 1)?;
+
+    let mut res = execute_approve(deps, env, owner, msg.spender, msg.token_id, msg.expires)?;
+    res.attributes.push(attr("permit_nonce", msg.nonce));
+
+    Ok(res)
+}
+
 fn execute_approve_for_all(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     sender: String,
     operator: String,
     approved: bool,
+    expires: Option<Expiration>,
 ) -> Result<Response, ContractError> {
     if sender.eq(&operator) {
         return Err(ContractError::InvalidAddress{});
     }
-    write_operators_store(deps.storage, sender.clone(), operator.clone(), approved)?;
+
+    if approved {
+        let expires = expires.unwrap_or_default();
+        if expires.is_expired(&env.block) {
+            return Err(ContractError::Expired{});
+        }
+        write_operators_store(deps.storage, sender.clone(), operator.clone(), expires)?;
+    } else {
+        remove_operators_store(deps.storage, sender.clone(), operator.clone());
+    }
 
     let res = Response {
         submessages: vec![],
@@ -228,33 +845,529 @@ fn execute_approve_for_all(
     Ok(res)
 }
 
-fn execute_mint(
+fn execute_revoke(
     deps: DepsMut,
-    _env: Env,
-    owner: String,
-    name: String,
-    uri: String,
+    env: Env,
+    sender: String,
+    spender: String,
+    value: String,
 ) -> Result<Response, ContractError> {
-    // generate token
-    let new_token_id = make_token_id(deps.storage)?;
-    let new_token = Token::new(new_token_id.clone(), name, uri);
-
-    write_token_store(deps.storage, new_token_id.clone(), new_token)?;
-
-    write_token_owner_store(deps.storage, new_token_id.clone(), owner.clone())?;
-
-    let mut token_id_set = read_owner_tokens_store(deps.storage, owner.clone())?;
+    let token_id = TokenId::new(value);
+    if !check_be_able_to_approve(deps.storage, &env.block, &token_id, sender.clone())? {
+        return Err(ContractError::CanNotApprove{});
+    }
 
-    token_id_set.push(new_token_id.clone());
-    write_minted_token_id_store(deps.storage, token_id_set.clone())?;
-    write_owner_tokens_store(deps.storage, owner.clone(), token_id_set)?;
+    if read_token_approval_store(deps.storage, token_id.clone(), spender.clone())?.is_none() {
+        return Err(ContractError::NotExistTokenAllowance{});
+    }
+    remove_token_approval_store(deps.storage, token_id.clone(), spender.clone());
 
     let res = Response {
         submessages: vec![],
         messages: vec![],
         attributes: vec![
-            attr("action", "mint"),
-            attr("token_id", &new_token_id.as_string()),
+            attr("action", "revoke"),
+            attr("sender", sender),
+            attr("spender", spender),
+            attr("token_id", &token_id.as_string()),
+            ],
+        data: None,
+    };
+
+    Ok(res)
+}
+
+fn execute_revoke_all(
+    deps: DepsMut,
+    _env: Env,
+    sender: String,
+    operator: String,
+) -> Result<Response, ContractError> {
+    remove_operators_store(deps.storage, sender.clone(), operator.clone());
+
+    let res = Response {
+        submessages: vec![],
+        messages: vec![],
+        attributes: vec![
+            attr("action", "revoke_all"),
+            attr("sender", sender),
+            attr("operator", operator),
+            ],
+        data: None,
+    };
+
+    Ok(res)
+}
+
+fn execute_mint(
+    deps: DepsMut,
+    env: Env,
+    sender: String,
+    msg: MintMsg,
+) -> Result<Response, ContractError> {
+    let minter = check_minter(deps.storage, &sender)?;
+
+    // burned tokens keep counting against the max supply
+    let minted_count = read_minted_count_store(deps.storage)?;
+    if minter.max_supply.map_or(false, |max_supply| minted_count >= max_supply) {
+        return Err(ContractError::MaxSupplyReached {});
+    }
+    write_minted_count_store(deps.storage, minted_count + 1)?;
+    let num_tokens = read_num_tokens_store(deps.storage)?;
+    write_num_tokens_store(deps.storage, num_tokens + 1)?;
+
+    let state = read_state_store(deps.storage)?;
+    let (owner, new_token_id) = mint_token(deps.storage, deps.api, &env.block, &state, sender, msg)?;
+
+    update_owner_tokens_store(deps.storage, new_token_id.clone(), owner.clone(), true)?;
+    write_snapshot(deps.storage, &env.block, &[new_token_id.clone()], &[owner.clone()])?;
+
+    let res = Response {
+        submessages: vec![],
+        messages: vec![],
+        attributes: vec![
+            attr("action", "mint"),
+            attr("owner", owner),
+            attr("token_id", &new_token_id.as_string()),
+            ],
+        data: None,
+    };
+
+    Ok(res)
+}
+
+fn execute_batch_mint(
+    deps: DepsMut,
+    env: Env,
+    sender: String,
+    tokens: Vec<MintMsg>,
+) -> Result<Response, ContractError> {
+    if tokens.is_empty() || tokens.len() > MAX_BATCH_SIZE {
+        return Err(ContractError::InvalidBatchSize {});
+    }
+
+    let minter = check_minter(deps.storage, &sender)?;
+
+    let minted_count = read_minted_count_store(deps.storage)? + tokens.len() as u64;
+    if minter.max_supply.map_or(false, |max_supply| minted_count > max_supply) {
+        return Err(ContractError::MaxSupplyReached {});
+    }
+    write_minted_count_store(deps.storage, minted_count)?;
+    let num_tokens = read_num_tokens_store(deps.storage)?;
+    write_num_tokens_store(deps.storage, num_tokens + tokens.len() as u64)?;
+
+    // owner index and balance are written once per owner
+    let state = read_state_store(deps.storage)?;
+    let mut minted: BTreeMap<String, Vec<TokenId>> = BTreeMap::new();
+    let mut token_ids: Vec<TokenId> = vec![];
+    for msg in tokens {
+        let (owner, new_token_id) = mint_token(deps.storage, deps.api, &env.block, &state, sender.clone(), msg)?;
+        token_ids.push(new_token_id.clone());
+        minted.entry(owner).or_default().push(new_token_id);
+    }
+    let mut owners: Vec<String> = vec![];
+    for (owner, owner_token_ids) in minted {
+        add_owner_tokens_store(deps.storage, owner.clone(), &owner_token_ids)?;
+        owners.push(owner);
+    }
+    write_snapshot(deps.storage, &env.block, &token_ids, &owners)?;
+
+    let token_ids: Vec<String> = token_ids.iter().map(|token_id| token_id.as_string()).collect();
+
+    let res = Response {
+        submessages: vec![],
+        messages: vec![],
+        attributes: vec![
+            attr("action", "batch_mint"),
+            attr("minter", sender),
+            attr("token_ids", token_ids.join(",")),
+            ],
+        data: None,
+    };
+
+    Ok(res)
+}
+
+/// Writes a new token and its owner. The caller checks the minter and the
+/// supply, and indexes the token under its owner.
+fn mint_token(
+    store: &mut dyn Storage,
+    api: &dyn Api,
+    block: &BlockInfo,
+    state: &State,
+    sender: String,
+    msg: MintMsg,
+) -> Result<(String, TokenId), ContractError> {
+    let royalty = match msg.royalty {
+        Some(v) => Some(validate_royalty(api, v)?),
+        None => None,
+    };
+
+    let owner = match msg.owner {
+        Some(v) => api.addr_validate(&v)?.to_string(),
+        None => sender,
+    };
+
+    // generate token
+    let new_token_id = match (state.token_id_mode, msg.token_id) {
+        (TokenIdMode::Sequential, None) => make_token_id(store)?,
+        (TokenIdMode::Custom, Some(v)) if is_valid_token_id(&v) => TokenId::new(v),
+        _ => return Err(ContractError::InvalidTokenId {}),
+    };
+    if read_token_store(store, new_token_id.clone())?.is_some() {
+        return Err(ContractError::TokenAlreadyExists {});
+    }
+    let transferable = msg.transferable.unwrap_or(state.transferable);
+    let new_token = Token::new(new_token_id.clone(), msg.name, msg.uri, msg.extension, royalty, transferable);
+
+    write_token_store(store, new_token_id.clone(), new_token)?;
+
+    write_token_owner_store(store, new_token_id.clone(), owner.clone())?;
+    add_token_history_store(store, new_token_id.clone(), owner.clone(), block, TransferType::Mint)?;
+
+    Ok((owner, new_token_id))
+}
+
+fn execute_update_metadata(
+    deps: DepsMut,
+    _env: Env,
+    sender: String,
+    value: String,
+    extension: Option<Metadata>,
+    freeze_metadata: Option<bool>,
+) -> Result<Response, ContractError> {
+    check_minter(deps.storage, &sender)?;
+
+    let token_id = TokenId::new(value);
+    let mut token = match read_token_store(deps.storage, token_id.clone())? {
+        Some(v) => v,
+        None => return Err(ContractError::NotExistToken {}),
+    };
+    if token.metadata_frozen {
+        return Err(ContractError::MetadataFrozen {});
+    }
+
+    token.extension = extension;
+    token.metadata_frozen = freeze_metadata.unwrap_or(false);
+    write_token_store(deps.storage, token_id.clone(), token)?;
+
+    let res = Response {
+        submessages: vec![],
+        messages: vec![],
+        attributes: vec![
+            attr("action", "update_metadata"),
+            attr("sender", sender),
+            attr("token_id", &token_id.as_string()),
+            ],
+        data: None,
+    };
+
+    Ok(res)
+}
+
+fn execute_transfer_minter(
+    deps: DepsMut,
+    _env: Env,
+    sender: String,
+    new_minter: String,
+) -> Result<Response, ContractError> {
+    let mut minter = check_minter(deps.storage, &sender)?;
+    minter.minter = deps.api.addr_validate(&new_minter)?.to_string();
+    write_minter_store(deps.storage, minter)?;
+
+    let res = Response {
+        submessages: vec![],
+        messages: vec![],
+        attributes: vec![
+            attr("action", "transfer_minter"),
+            attr("sender", sender),
+            attr("minter", new_minter),
+            ],
+        data: None,
+    };
+
+    Ok(res)
+}
+
+fn execute_burn(
+    deps: DepsMut,
+    env: Env,
+    sender: String,
+    value: String,
+) -> Result<Response, ContractError> {
+    let token_id = TokenId::new(value);
+
+    let owner = match read_token_owner_store(deps.storage, token_id.clone())? {
+        Some(v) => v,
+        None => return Err(ContractError::NotExistToken {}),
+    };
+
+    if !check_be_able_to_send(deps.storage, &env.block, &token_id, sender.clone())? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // soulbound tokens can only be burned by their owner
+    if sender != owner && check_transferable(deps.storage, &token_id).is_err() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if read_token_lock_store(deps.storage, token_id.clone())?.is_some() {
+        return Err(ContractError::TokenLocked {});
+    }
+
+    remove_token_store(deps.storage, token_id.clone());
+    remove_token_owner_store(deps.storage, token_id.clone());
+    remove_token_approvals_store(deps.storage, token_id.clone())?;
+    remove_token_user_store(deps.storage, token_id.clone());
+    update_owner_tokens_store(deps.storage, token_id.clone(), owner.clone(), false)?;
+    write_snapshot(deps.storage, &env.block, &[token_id.clone()], &[owner.clone()])?;
+
+    let num_tokens = read_num_tokens_store(deps.storage)?;
+    write_num_tokens_store(deps.storage, num_tokens.saturating_sub(1))?;
+
+    let res = Response {
+        submessages: vec![],
+        messages: vec![],
+        attributes: vec![
+            attr("action", "burn"),
+            attr("sender", sender),
+            attr("owner", owner),
+            attr("token_id", &token_id.as_string()),
+            ],
+        data: None,
+    };
+
+    Ok(res)
+}
+
+fn execute_lock(
+    deps: DepsMut,
+    env: Env,
+    sender: String,
+    value: String,
+    reason: String,
+    unlock_authority: Option<String>,
+) -> Result<Response, ContractError> {
+    let token_id = TokenId::new(value);
+
+    if read_token_owner_store(deps.storage, token_id.clone())?.is_none() {
+        return Err(ContractError::NotExistToken {});
+    }
+
+    // approved spenders of the token cannot lock it away from its owner
+    if !check_be_able_to_approve(deps.storage, &env.block, &token_id, sender.clone())? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if read_token_lock_store(deps.storage, token_id.clone())?.is_some() {
+        return Err(ContractError::TokenLocked {});
+    }
+
+    let unlock_authority = match unlock_authority {
+        Some(v) => deps.api.addr_validate(&v)?.to_string(),
+        None => sender.clone(),
+    };
+    let lock = Lock { locked_by: sender.clone(), reason: reason.clone(), unlock_authority: unlock_authority.clone() };
+    write_token_lock_store(deps.storage, token_id.clone(), lock)?;
+
+    let res = Response {
+        submessages: vec![],
+        messages: vec![],
+        attributes: vec![
+            attr("action", "lock"),
+            attr("sender", sender),
+            attr("token_id", &token_id.as_string()),
+            attr("reason", reason),
+            attr("unlock_authority", unlock_authority),
+            ],
+        data: None,
+    };
+
+    Ok(res)
+}
+
+fn execute_unlock(
+    deps: DepsMut,
+    _env: Env,
+    sender: String,
+    value: String,
+) -> Result<Response, ContractError> {
+    let token_id = TokenId::new(value);
+
+    let lock = match read_token_lock_store(deps.storage, token_id.clone())? {
+        Some(v) => v,
+        None => return Err(ContractError::TokenNotLocked {}),
+    };
+
+    if lock.unlock_authority != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    remove_token_lock_store(deps.storage, token_id.clone());
+
+    let res = Response {
+        submessages: vec![],
+        messages: vec![],
+        attributes: vec![
+            attr("action", "unlock"),
+            attr("sender", sender),
+            attr("token_id", &token_id.as_string()),
+            ],
+        data: None,
+    };
+
+    Ok(res)
+}
+
+fn execute_set_user(
+    deps: DepsMut,
+    env: Env,
+    sender: String,
+    value: String,
+    user: Option<String>,
+    expires: Expiration,
+) -> Result<Response, ContractError> {
+    let token_id = TokenId::new(value);
+
+    if read_token_owner_store(deps.storage, token_id.clone())?.is_none() {
+        return Err(ContractError::NotExistToken {});
+    }
+
+    if !check_be_able_to_send(deps.storage, &env.block, &token_id, sender.clone())? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let user = match user {
+        Some(v) => {
+            if expires.is_expired(&env.block) {
+                return Err(ContractError::Expired {});
+            }
+            let user = deps.api.addr_validate(&v)?.to_string();
+            write_token_user_store(deps.storage, token_id.clone(), TokenUser { user: user.clone(), expires })?;
+            user
+        }
+        None => {
+            remove_token_user_store(deps.storage, token_id.clone());
+            String::new()
+        }
+    };
+
+    let res = Response {
+        submessages: vec![],
+        messages: vec![],
+        attributes: vec![
+            attr("action", "set_user"),
+            attr("sender", sender),
+            attr("token_id", &token_id.as_string()),
+            attr("user", user),
+            ],
+        data: None,
+    };
+
+    Ok(res)
+}
+
+fn execute_reveal(
+    deps: DepsMut,
+    _env: Env,
+    sender: String,
+    base_uri: String,
+) -> Result<Response, ContractError> {
+    let mut state = read_state_store(deps.storage)?;
+    check_admin(&state, &sender)?;
+
+    if state.placeholder_uri.is_none() {
+        return Err(ContractError::NothingToReveal {});
+    }
+    if state.revealed {
+        return Err(ContractError::AlreadyRevealed {});
+    }
+
+    state.base_token_uri = Some(base_uri.clone());
+    state.revealed = true;
+    write_state_store(deps.storage, state)?;
+
+    let res = Response {
+        submessages: vec![],
+        messages: vec![],
+        attributes: vec![
+            attr("action", "reveal"),
+            attr("sender", sender),
+            attr("base_uri", base_uri),
+            ],
+        data: None,
+    };
+
+    Ok(res)
+}
+
+fn execute_set_auction(
+    deps: DepsMut,
+    _env: Env,
+    sender: String,
+    auction: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut state = read_state_store(deps.storage)?;
+    check_admin(&state, &sender)?;
+
+    state.auction = match auction {
+        Some(v) => Some(deps.api.addr_validate(&v)?.to_string()),
+        None => None,
+    };
+    let auction = state.auction.clone().unwrap_or_default();
+    write_state_store(deps.storage, state)?;
+
+    let res = Response {
+        submessages: vec![],
+        messages: vec![],
+        attributes: vec![
+            attr("action", "set_auction"),
+            attr("sender", sender),
+            attr("auction", auction),
+            ],
+        data: None,
+    };
+
+    Ok(res)
+}
+
+fn execute_update_collection_info(
+    deps: DepsMut,
+    _env: Env,
+    sender: String,
+    description: Option<String>,
+    image: Option<String>,
+    external_link: Option<String>,
+    base_token_uri: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut state = read_state_store(deps.storage)?;
+    check_admin(&state, &sender)?;
+
+    // hidden collections get their base token uri from the reveal only
+    if base_token_uri.is_some() && state.placeholder_uri.is_some() {
+        return Err(ContractError::RevealOnly {});
+    }
+
+    if description.is_some() {
+        state.description = description;
+    }
+    if image.is_some() {
+        state.image = image;
+    }
+    if external_link.is_some() {
+        state.external_link = external_link;
+    }
+    if base_token_uri.is_some() {
+        state.base_token_uri = base_token_uri;
+    }
+    write_state_store(deps.storage, state)?;
+
+    let res = Response {
+        submessages: vec![],
+        messages: vec![],
+        attributes: vec![
+            attr("action", "update_collection_info"),
+            attr("sender", sender),
             ],
         data: None,
     };
@@ -266,120 +1379,581 @@ fn balance(
     deps: Deps,
     address: String,
 ) -> StdResult<Binary> {
-    let res = read_owner_tokens_store(deps.storage, address)?;
-    Ok(to_binary(&res.len())?)
+    let res = read_balance_store(deps.storage, address)?;
+    Ok(to_binary(&res)?)
 }
 
 fn owner(
     deps: Deps,
-    value: Uint128,
+    value: String,
 ) -> StdResult<Binary> {
-    let address = read_token_owner_store(deps.storage, TokenId::new(value))?;
-    
+    let address = read_token_owner_store(deps.storage, TokenId::new(value))?
+        .ok_or_else(|| StdError::not_found("token"))?;
+
     Ok(to_binary(&address)?)
 }
 
 fn allowance(
     deps: Deps,
-    value: Uint128,
+    env: Env,
+    value: String,
 ) -> StdResult<Binary> {
     let token_id = TokenId::new(value);
-    let res = read_token_approvals_store(deps.storage, token_id)?;
+    let res = read_token_approvals_store(deps.storage, token_id)?
+        .into_iter()
+        .find(|approval| !approval.expires.is_expired(&env.block))
+        .map(|approval| approval.spender);
 
     Ok(to_binary(&res)?)
 }
 
 fn token(
     deps: Deps,
-    value: Uint128,
+    value: String,
 ) -> StdResult<Binary> {
     let token_id = TokenId::new(value);
-    let res = read_token_store(deps.storage, token_id)?;
+    let mut res = read_token_store(deps.storage, token_id)?
+        .ok_or_else(|| StdError::not_found("token"))?;
+    res.uri = resolve_token_uri(&read_state_store(deps.storage)?, &res);
 
     Ok(to_binary(&res)?)
 }
 
-fn make_token_id(store: &mut dyn Storage) -> StdResult<TokenId> {
-    let new_token_id = match get_current_token_id(store)? {
-        Some(v) => v.as_u128().add(Uint128(1)),
-        None => Uint128(0),
+fn minter(
+    deps: Deps,
+) -> StdResult<Binary> {
+    let minter = minter_resolver_read(deps.storage).load()?;
+    let res = MinterResponse {
+        minter: minter.minter,
+        max_supply: minter.max_supply,
     };
 
-    Ok(TokenId::new(new_token_id))
+    Ok(to_binary(&res)?)
 }
 
-fn get_current_token_id(store: &dyn Storage) -> StdResult<Option<TokenId>> {
-    let token_id_set = match read_minted_token_id_store(store)? {
-        Some(record) => record,
-        _ => return Ok(None),
+fn num_tokens(
+    deps: Deps,
+) -> StdResult<Binary> {
+    let res = NumTokensResponse {
+        count: read_num_tokens_store(deps.storage)?,
     };
 
-    let last_token_id = token_id_set.last().unwrap().clone();
-    Ok(Some(last_token_id))
+    Ok(to_binary(&res)?)
 }
 
-fn validate_token_id(store: &dyn Storage, token_id: &TokenId) -> bool {
-    let current_token_id = get_current_token_id(store).unwrap();
-    if current_token_id.unwrap().as_u128() < token_id.as_u128() {
-        return false;
+fn owner_of(
+    deps: Deps,
+    env: Env,
+    value: String,
+    include_expired: bool,
+) -> StdResult<Binary> {
+    let res = token_access(deps, &env.block, &TokenId::new(value), include_expired)?;
+
+    Ok(to_binary(&res)?)
+}
+
+fn approval(
+    deps: Deps,
+    env: Env,
+    value: String,
+    spender: String,
+    include_expired: bool,
+) -> StdResult<Binary> {
+    let approval = token_approvals(deps.storage, &env.block, &TokenId::new(value), include_expired)?
+        .into_iter()
+        .find(|approval| approval.spender == spender)
+        .ok_or_else(|| StdError::not_found("approval"))?;
+
+    Ok(to_binary(&ApprovalResponse { approval })?)
+}
+
+fn approvals(
+    deps: Deps,
+    env: Env,
+    value: String,
+    include_expired: bool,
+) -> StdResult<Binary> {
+    let approvals = token_approvals(deps.storage, &env.block, &TokenId::new(value), include_expired)?;
+
+    Ok(to_binary(&ApprovalsResponse { approvals })?)
+}
+
+fn all_operators(
+    deps: Deps,
+    env: Env,
+    owner: String,
+    include_expired: bool,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|operator| exclusive_start(operator.into_bytes()));
+
+    let operators = operators_resolver_read(deps.storage, &owner)
+        .range(start.as_deref(), None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((_, expires)) => include_expired || !expires.is_expired(&env.block),
+            Err(_) => true,
+        })
+        .take(limit)
+        .map(|item| {
+            let (key, expires) = item?;
+            let spender = String::from_utf8(key).map_err(StdError::invalid_utf8)?;
+            Ok(Approval { spender, expires })
+        })
+        .collect::<StdResult<Vec<Approval>>>()?;
+
+    Ok(to_binary(&OperatorsResponse { operators })?)
+}
+
+fn is_approved_for_all(
+    deps: Deps,
+    env: Env,
+    owner: String,
+    operator: String,
+) -> StdResult<Binary> {
+    let approved = match read_operators_store(deps.storage, owner, operator)? {
+        Some(expires) => !expires.is_expired(&env.block),
+        None => false,
+    };
+
+    Ok(to_binary(&IsApprovedForAllResponse { approved })?)
+}
+
+fn lock_status(
+    deps: Deps,
+    value: String,
+) -> StdResult<Binary> {
+    let lock = read_token_lock_store(deps.storage, TokenId::new(value))?;
+
+    Ok(to_binary(&LockStatusResponse { locked: lock.is_some(), lock })?)
+}
+
+fn permit_nonce(
+    deps: Deps,
+    owner: String,
+) -> StdResult<Binary> {
+    let nonce = read_permit_nonce_store(deps.storage, owner)?;
+
+    Ok(to_binary(&PermitNonceResponse { nonce })?)
+}
+
+fn user_of(
+    deps: Deps,
+    env: Env,
+    value: String,
+) -> StdResult<Binary> {
+    let res = match read_token_user_store(deps.storage, TokenId::new(value))? {
+        Some(token_user) if !token_user.expires.is_expired(&env.block) => UserOfResponse {
+            user: Some(token_user.user),
+            expires: Some(token_user.expires),
+        },
+        _ => UserOfResponse { user: None, expires: None },
+    };
+
+    Ok(to_binary(&res)?)
+}
+
+fn owner_at(
+    deps: Deps,
+    value: String,
+    height: u64,
+) -> StdResult<Binary> {
+    check_snapshots(deps.storage)?;
+    let owner = read_owner_at_store(deps.storage, TokenId::new(value), height)?;
+
+    Ok(to_binary(&OwnerAtResponse { owner })?)
+}
+
+fn balance_at(
+    deps: Deps,
+    address: String,
+    height: u64,
+) -> StdResult<Binary> {
+    check_snapshots(deps.storage)?;
+    let balance = read_balance_at_store(deps.storage, address, height)?;
+
+    Ok(to_binary(&BalanceAtResponse { balance })?)
+}
+
+fn history(
+    deps: Deps,
+    value: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = match start_after {
+        Some(index) => match index.checked_add(1) {
+            Some(v) => Some(v.to_be_bytes().to_vec()),
+            None => return Ok(to_binary(&HistoryResponse { history: vec![] })?),
+        },
+        None => None,
+    };
+
+    let history = token_history_resolver_read(deps.storage, &TokenId::new(value))
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, record)| record))
+        .collect::<StdResult<Vec<HistoryRecord>>>()?;
+
+    Ok(to_binary(&HistoryResponse { history })?)
+}
+
+fn provenance(
+    deps: Deps,
+) -> StdResult<Binary> {
+    let state = read_state_store(deps.storage)?;
+    let res = ProvenanceResponse {
+        provenance_hash: state.provenance_hash,
+        revealed: state.revealed,
+    };
+
+    Ok(to_binary(&res)?)
+}
+
+fn transferable(
+    deps: Deps,
+    value: String,
+) -> StdResult<Binary> {
+    let token = read_token_store(deps.storage, TokenId::new(value))?
+        .ok_or_else(|| StdError::not_found("token"))?;
+
+    Ok(to_binary(&TransferableResponse { transferable: token.transferable })?)
+}
+
+fn royalty_info(
+    deps: Deps,
+    value: String,
+    sale_price: Uint128,
+) -> StdResult<Binary> {
+    let token = read_token_store(deps.storage, TokenId::new(value))?
+        .ok_or_else(|| StdError::not_found("token"))?;
+
+    let res = match token.royalty.or(read_royalty_store(deps.storage)?) {
+        Some(royalty) => RoyaltyInfoResponse {
+            address: royalty.payment_address,
+            royalty_amount: sale_price.multiply_ratio(royalty.royalty_bps, MAX_ROYALTY_BPS),
+        },
+        None => RoyaltyInfoResponse {
+            address: String::new(),
+            royalty_amount: Uint128::zero(),
+        },
+    };
+
+    Ok(to_binary(&res)?)
+}
+
+fn contract_info(
+    deps: Deps,
+) -> StdResult<Binary> {
+    let state = read_state_store(deps.storage)?;
+    let res = ContractInfoResponse {
+        name: state.name,
+        symbol: state.symbol,
+        description: state.description,
+        image: state.image,
+        external_link: state.external_link,
+        // stays hidden until the reveal
+        base_token_uri: if state.placeholder_uri.is_some() && !state.revealed { None } else { state.base_token_uri },
+        admin: state.admin,
+        auction: state.auction,
+    };
+
+    Ok(to_binary(&res)?)
+}
+
+fn nft_info(
+    deps: Deps,
+    value: String,
+) -> StdResult<Binary> {
+    let res = token_info(deps, &TokenId::new(value))?;
+
+    Ok(to_binary(&res)?)
+}
+
+fn all_nft_info(
+    deps: Deps,
+    env: Env,
+    value: String,
+    include_expired: bool,
+) -> StdResult<Binary> {
+    let token_id = TokenId::new(value);
+    let res = AllNftInfoResponse {
+        access: token_access(deps, &env.block, &token_id, include_expired)?,
+        info: token_info(deps, &token_id)?,
+    };
+
+    Ok(to_binary(&res)?)
+}
+
+fn tokens(
+    deps: Deps,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|start| exclusive_start(TokenId::new(start).as_bytes()));
+
+    let tokens = owner_token_index_resolver_read(deps.storage, &owner)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, token_id)| token_id.as_string()))
+        .collect::<StdResult<Vec<String>>>()?;
+
+    Ok(to_binary(&TokensResponse { tokens })?)
+}
+
+fn all_tokens(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|start| exclusive_start(TokenId::new(start).as_bytes()));
+
+    let tokens = token_resolver_read(deps.storage)
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, token)| token.token_id.as_string()))
+        .collect::<StdResult<Vec<String>>>()?;
+
+    Ok(to_binary(&TokensResponse { tokens })?)
+}
+
+fn token_access(
+    deps: Deps,
+    block: &BlockInfo,
+    token_id: &TokenId,
+    include_expired: bool,
+) -> StdResult<OwnerOfResponse> {
+    let owner = read_token_owner_store(deps.storage, token_id.clone())?
+        .ok_or_else(|| StdError::not_found("token"))?;
+
+    Ok(OwnerOfResponse {
+        owner,
+        approvals: token_approvals(deps.storage, block, token_id, include_expired)?,
+    })
+}
+
+fn token_info(
+    deps: Deps,
+    token_id: &TokenId,
+) -> StdResult<NftInfoResponse> {
+    let token = read_token_store(deps.storage, token_id.clone())?
+        .ok_or_else(|| StdError::not_found("token"))?;
+
+    let token_uri = resolve_token_uri(&read_state_store(deps.storage)?, &token);
+
+    Ok(NftInfoResponse {
+        name: token.name,
+        token_uri: Some(token_uri),
+        extension: token.extension,
+    })
+}
+
+/// Hidden collections show the placeholder until the reveal and
+/// `base_token_uri + token_id` afterwards. Otherwise the base token uri only
+/// fills in for tokens minted without a uri.
+fn resolve_token_uri(
+    state: &State,
+    token: &Token,
+) -> String {
+    if let Some(placeholder_uri) = &state.placeholder_uri {
+        if !state.revealed {
+            return placeholder_uri.clone();
+        }
+    }
+
+    match &state.base_token_uri {
+        Some(base_token_uri) if token.uri.is_empty() || state.placeholder_uri.is_some() => {
+            base_token_uri.clone() + &token.token_id.as_string()
+        }
+        _ => token.uri.clone(),
+    }
+}
+
+/// Checkpoints the current owners of `token_ids` and the current balances of
+/// `owners` at this height. Does nothing unless the collection takes snapshots.
+fn write_snapshot(
+    store: &mut dyn Storage,
+    block: &BlockInfo,
+    token_ids: &[TokenId],
+    owners: &[String],
+) -> StdResult<()> {
+    if !read_state_store(store)?.snapshots {
+        return Ok(());
+    }
+
+    for token_id in token_ids {
+        let owner = read_token_owner_store(store, token_id.clone())?;
+        write_owner_checkpoint_store(store, token_id.clone(), block.height, owner)?;
+    }
+    for owner in owners {
+        let balance = read_balance_store(store, owner.clone())?;
+        write_balance_checkpoint_store(store, owner.clone(), block.height, balance)?;
+    }
+    Ok(())
+}
+
+fn check_snapshots(store: &dyn Storage) -> StdResult<()> {
+    if !read_state_store(store)?.snapshots {
+        return Err(StdError::generic_err("snapshots are not enabled for this collection"));
+    }
+    Ok(())
+}
+
+fn token_approvals(
+    store: &dyn Storage,
+    block: &BlockInfo,
+    token_id: &TokenId,
+    include_expired: bool,
+) -> StdResult<Vec<Approval>> {
+    let approvals = read_token_approvals_store(store, token_id.clone())?
+        .into_iter()
+        .filter(|approval| include_expired || !approval.expires.is_expired(block))
+        .collect();
+
+    Ok(approvals)
+}
+
+/// Turns a key into a range start that skips the key itself.
+fn exclusive_start(mut key: Vec<u8>) -> Vec<u8> {
+    key.push(0);
+    key
+}
+
+/// Approvals and the user granted by the previous owner do not carry over to the new owner.
+fn clear_token_grants(store: &mut dyn Storage, token_id: TokenId) -> StdResult<()> {
+    remove_token_approvals_store(store, token_id.clone())?;
+    remove_token_user_store(store, token_id);
+    Ok(())
+}
+
+fn make_token_id(store: &mut dyn Storage) -> StdResult<TokenId> {
+    let new_token_id = read_next_token_id_store(store)?;
+    write_next_token_id_store(store, Uint128(new_token_id.u128() + 1))?;
+
+    Ok(TokenId::new(new_token_id.to_string()))
+}
+
+fn validate_token_id(store: &dyn Storage, token_id: &TokenId) -> StdResult<bool> {
+    let token = read_token_store(store, token_id.clone())?;
+    Ok(token.is_some())
+}
+
+fn check_transferable(
+    store: &dyn Storage,
+    token_id: &TokenId,
+) -> Result<(), ContractError> {
+    match read_token_store(store, token_id.clone())? {
+        Some(token) if !token.transferable => Err(ContractError::NonTransferable {}),
+        _ => Ok(()),
+    }
+}
+
+fn check_admin(
+    state: &State,
+    sender: &str,
+) -> Result<(), ContractError> {
+    if state.admin.as_deref() != Some(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+fn check_auction(
+    store: &dyn Storage,
+    caller: &str,
+) -> Result<(), ContractError> {
+    if read_state_store(store)?.auction.as_deref() != Some(caller) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+fn check_minter(
+    store: &dyn Storage,
+    sender: &str,
+) -> Result<Minter, ContractError> {
+    match read_minter_store(store)? {
+        Some(minter) if minter.minter == sender => Ok(minter),
+        _ => Err(ContractError::Unauthorized {}),
     }
-    return true
 }
 
 fn check_be_able_to_approve(
     store: &dyn Storage,
+    block: &BlockInfo,
     token_id: &TokenId,
     sender: String,
-) -> bool {
+) -> StdResult<bool> {
 
-    let (token_owner, is_owner) = validate_token_owner(store, token_id, sender.clone());
+    let (token_owner, is_owner) = validate_token_owner(store, token_id, sender.clone())?;
     if is_owner {
-        return true;
+        return Ok(true);
     }
+    let token_owner = match token_owner {
+        Some(v) => v,
+        None => return Ok(false),
+    };
 
-    let op = read_operators_store(store, token_owner.unwrap(), sender).unwrap();
-    if op.is_none() {
-        return false;
+    match read_operators_store(store, token_owner, sender)? {
+        Some(expires) => Ok(!expires.is_expired(block)),
+        None => Ok(false),
     }
-    if op.unwrap() {
-        return true;
+}
+
+fn check_be_able_to_send(
+    store: &dyn Storage,
+    block: &BlockInfo,
+    token_id: &TokenId,
+    spender: String,
+) -> StdResult<bool> {
+    if check_be_able_to_approve(store, block, token_id, spender.clone())? {
+        return Ok(true);
     }
 
-    return false;
+    validate_allowance(store, block, token_id, spender)
 }
 
 fn validate_token_owner(
     store: &dyn Storage,
     token_id: &TokenId,
     addr: String,
-) -> (Option<String>, bool) {
-    let token_owner: Option<String> = read_token_owner_store(store, token_id.clone()).unwrap();
+) -> StdResult<(Option<String>, bool)> {
+    let token_owner: Option<String> = read_token_owner_store(store, token_id.clone())?;
 
-    if token_owner.clone().is_none() {
-        return (None, false);
-    } 
+    let is_owner = token_owner.as_deref() == Some(addr.as_str());
 
-    if !token_owner.clone().unwrap().eq(&addr) {
-        return (token_owner, false);
-    }
-
-    return (token_owner, true);
+    Ok((token_owner, is_owner))
 }
 
 fn validate_allowance(
     store: &dyn Storage,
+    block: &BlockInfo,
     token_id: &TokenId,
     addr: String,
-) -> bool {
-    let token_approval = read_token_approvals_store(store, token_id.clone()).unwrap();
-    if token_approval.is_none() {
-        return false
+) -> StdResult<bool> {
+    match read_token_approval_store(store, token_id.clone(), addr)? {
+        Some(expires) => Ok(!expires.is_expired(block)),
+        None => Ok(false),
     }
+}
 
-    if !token_approval.unwrap().eq(&addr) {
-        return false;
+fn validate_royalty(
+    api: &dyn Api,
+    royalty: Royalty,
+) -> Result<Royalty, ContractError> {
+    if royalty.royalty_bps > MAX_ROYALTY_BPS {
+        return Err(ContractError::InvalidRoyalty {});
     }
-    return true;
+
+    Ok(Royalty {
+        payment_address: api.addr_validate(&royalty.payment_address)?.to_string(),
+        royalty_bps: royalty.royalty_bps,
+    })
 }
 
 fn is_valid_name(name: &str) -> bool {
@@ -389,6 +1963,12 @@ fn is_valid_name(name: &str) -> bool {
     return true;
 }
 
+fn is_valid_token_id(token_id: &str) -> bool {
+    !token_id.is_empty()
+        && token_id.chars().count() <= 128
+        && !token_id.chars().any(|c| c.is_control() || c.is_whitespace())
+}
+
 fn is_valid_symbol(symbol: &str) -> bool {
     if symbol.chars().count() < 3 || symbol.chars().count() > 30 {
         return false;
@@ -33,4 +33,58 @@ pub enum ContractError {
     #[error("Err invalid address")]
     InvalidAddress {},
 
+    #[error("Err unauthorized")]
+    Unauthorized {},
+
+    #[error("Err approval already expired")]
+    Expired {},
+
+    #[error("Err max supply reached")]
+    MaxSupplyReached {},
+
+    #[error("Err token metadata is frozen")]
+    MetadataFrozen {},
+
+    #[error("Err invalid token id")]
+    InvalidTokenId {},
+
+    #[error("Err token already exists")]
+    TokenAlreadyExists {},
+
+    #[error("Err invalid royalty")]
+    InvalidRoyalty {},
+
+    #[error("Err invalid batch size")]
+    InvalidBatchSize {},
+
+    #[error("Err duplicate token id")]
+    DuplicateTokenId {},
+
+    #[error("Err token is locked")]
+    TokenLocked {},
+
+    #[error("Err token is not locked")]
+    TokenNotLocked {},
+
+    #[error("Err token is non-transferable")]
+    NonTransferable {},
+
+    #[error("Err collection already revealed")]
+    AlreadyRevealed {},
+
+    #[error("Err base token uri can only be set by reveal")]
+    RevealOnly {},
+
+    #[error("Err collection has no placeholder to reveal")]
+    NothingToReveal {},
+
+    #[error("Err invalid permit signature")]
+    InvalidSignature {},
+
+    #[error("Err invalid permit nonce")]
+    InvalidNonce {},
+
+    #[error("Err minter is required to migrate this collection")]
+    MinterRequired {},
+
 }
@@ -5,6 +5,9 @@ pub mod msg;
 pub mod store;
 pub mod state;
 
+#[cfg(feature = "dynamic-link")]
+mod callable_points;
+
 pub use crate::msg::InstantiateMsg;
 pub use crate::msg::ExecuteMsg;
 
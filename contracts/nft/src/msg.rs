@@ -1,12 +1,49 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Uint128};
+use cosmwasm_std::{to_binary, Binary, CosmosMsg, StdResult, Uint128, WasmMsg};
+
+use crate::state::{Approval, Expiration, HistoryRecord, Lock, Metadata, Royalty, TokenIdMode};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub name: String,
     pub symbol: String,
+    /// address allowed to mint, defaults to the instantiator
+    pub minter: Option<String>,
+    /// caps the number of tokens ever minted, burning a token does not free up supply
+    pub max_supply: Option<u64>,
+    /// defaults to sequential ids
+    pub token_id_mode: Option<TokenIdMode>,
+    /// applies to every token minted without its own royalty
+    pub royalty: Option<Royalty>,
+    /// `false` makes the collection soulbound by default, defaults to `true`
+    pub transferable: Option<bool>,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub external_link: Option<String>,
+    pub base_token_uri: Option<String>,
+    /// may update the collection info, defaults to the instantiator
+    pub admin: Option<String>,
+    /// hides the token uris until `Reveal`, cannot be combined with `base_token_uri`
+    pub placeholder_uri: Option<String>,
+    pub provenance_hash: Option<String>,
+    /// enables the `OwnerAt` and `BalanceAt` queries at the cost of extra writes
+    pub snapshots: Option<bool>,
+    /// trusted auction contract allowed to escrow and settle tokens through the dynamic link
+    pub auction: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {
+    /// minter of collections instantiated before the minter role, defaults to the admin.
+    /// Ignored when the collection already has a minter
+    pub minter: Option<String>,
+    pub max_supply: Option<u64>,
+    /// number of legacy owner token lists, approvals, operators and scanned tokens handled per
+    /// migration, defaults to `DEFAULT_MIGRATE_LIMIT`. Large collections migrate again until
+    /// every `*_migrated` and `tokens_scanned` attribute is `true`
+    pub limit: Option<u32>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -14,32 +51,374 @@ pub struct InstantiateMsg {
 pub enum ExecuteMsg {
     Transfer {
         recipient: String,
-        token_id: Uint128,
+        token_id: String,
+        /// records the transfer as a sale in the token history, ignored unless the caller is the trusted auction
+        sale: Option<bool>,
     },
+    /// moves `token_id` from its owner `sender`, the caller must be the owner,
+    /// the approved spender of the token or an operator of the owner
     TransferFrom {
         sender: String,
         recipient: String,
-        token_id: Uint128,
+        token_id: String,
     },
     Approve {
+        #[serde(alias = "spender")]
         recipient: String,
-        token_id: Uint128,
+        token_id: String,
+        expires: Option<Expiration>,
     },
     ApproveForAll {
         opeartor: String,
         approved: bool,
+        expires: Option<Expiration>,
+    },
+    Revoke {
+        spender: String,
+        token_id: String,
+    },
+    RevokeAll {
+        operator: String,
+    },
+    Mint(MintMsg),
+    /// mints up to `MAX_BATCH_SIZE` tokens at once, all or nothing
+    BatchMint {
+        tokens: Vec<MintMsg>,
+    },
+    /// replaces the extension of a token, callable by the minter until the metadata is frozen
+    UpdateMetadata {
+        token_id: String,
+        extension: Option<Metadata>,
+        freeze_metadata: Option<bool>,
+    },
+    TransferMinter {
+        minter: String,
+    },
+    Burn {
+        token_id: String,
+    },
+    /// freezes the token in place, the caller must be the owner or an operator of the owner.
+    /// `unlock_authority` defaults to the caller
+    Lock {
+        token_id: String,
+        reason: String,
+        unlock_authority: Option<String>,
+    },
+    /// only the unlock authority of the lock can call this
+    Unlock {
+        token_id: String,
+    },
+    /// approves `spender` on behalf of the token owner who signed the permit off-chain.
+    /// Anyone can submit it, `nonce` must be the current permit nonce of the owner
+    Permit(PermitMsg),
+    /// lets `user` use the token until `expires`, `None` removes the user.
+    /// The caller must be the owner, the approved spender of the token or an operator of the owner
+    SetUser {
+        token_id: String,
+        user: Option<String>,
+        expires: Expiration,
+    },
+    /// admin only and only once for collections with a placeholder, token uris become `base_uri + token_id`
+    Reveal {
+        base_uri: String,
+    },
+    /// admin only, `None` disables the dynamic link callable points
+    SetAuction {
+        auction: Option<String>,
     },
-    Mint {
-        name: String,
-        uri: String,
+    /// admin only, fields left out keep their current value
+    UpdateCollectionInfo {
+        description: Option<String>,
+        image: Option<String>,
+        external_link: Option<String>,
+        base_token_uri: Option<String>,
     },
+    /// cw721 transfer, the caller must be the owner, the approved spender of the
+    /// token or an operator of the owner
+    TransferNft {
+        recipient: String,
+        token_id: String,
+    },
+    /// cw721 transfer to a contract, which is notified with `ReceiveNft`
+    SendNft {
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    },
+    /// moves up to `MAX_BATCH_SIZE` tokens to `recipient`, all or nothing
+    BatchTransfer {
+        recipient: String,
+        token_ids: Vec<String>,
+    },
+    /// cw721 name of `ApproveForAll { approved: true }`
+    ApproveAll {
+        operator: String,
+        expires: Option<Expiration>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PermitMsg {
+    pub spender: String,
+    pub token_id: String,
+    pub nonce: u64,
+    pub expires: Option<Expiration>,
+    /// compressed secp256k1 public key of the owner
+    pub pubkey: Binary,
+    /// signature over the sha256 hash of the json encoded `PermitPayload`
+    pub signature: Binary,
+}
+
+/// What the owner signs for a `Permit`, serialized to json in this field order.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PermitPayload {
+    pub spender: String,
+    pub token_id: String,
+    pub nonce: u64,
+    pub expires: Option<Expiration>,
+    pub chain_id: String,
+    pub contract_address: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MintMsg {
+    /// receives the new token, defaults to the minter
+    pub owner: Option<String>,
+    /// required in custom token id mode, not allowed in sequential mode
+    pub token_id: Option<String>,
+    pub name: String,
+    pub uri: String,
+    pub extension: Option<Metadata>,
+    pub royalty: Option<Royalty>,
+    /// defaults to the collection setting
+    pub transferable: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     Balance { address: String },
-    Owner { token_id: Uint128 },
-    Allowance { token_id: Uint128 },
-    Token { token_id: Uint128 },
+    Owner { token_id: String },
+    Allowance { token_id: String },
+    Token { token_id: String },
+    Minter {},
+    NumTokens {},
+    OwnerOf {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    Approval {
+        token_id: String,
+        spender: String,
+        include_expired: Option<bool>,
+    },
+    Approvals {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    AllOperators {
+        owner: String,
+        include_expired: Option<bool>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    IsApprovedForAll {
+        owner: String,
+        operator: String,
+    },
+    LockStatus {
+        token_id: String,
+    },
+    PermitNonce {
+        owner: String,
+    },
+    /// current user of the token, nothing once expired
+    UserOf {
+        token_id: String,
+    },
+    /// owner after the block at `height`, needs snapshots
+    OwnerAt {
+        token_id: String,
+        height: u64,
+    },
+    /// balance after the block at `height`, needs snapshots
+    BalanceAt {
+        address: String,
+        height: u64,
+    },
+    /// ownership records of the token, oldest first
+    History {
+        token_id: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    Provenance {},
+    Transferable {
+        token_id: String,
+    },
+    /// royalty owed on a sale of `token_id` for `sale_price`
+    RoyaltyInfo {
+        token_id: String,
+        sale_price: Uint128,
+    },
+    ContractInfo {},
+    NftInfo {
+        token_id: String,
+    },
+    AllNftInfo {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    AllTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MinterResponse {
+    pub minter: String,
+    pub max_supply: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NumTokensResponse {
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnerOfResponse {
+    pub owner: String,
+    pub approvals: Vec<Approval>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ApprovalResponse {
+    pub approval: Approval,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ApprovalsResponse {
+    pub approvals: Vec<Approval>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OperatorsResponse {
+    pub operators: Vec<Approval>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IsApprovedForAllResponse {
+    pub approved: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LockStatusResponse {
+    pub locked: bool,
+    pub lock: Option<Lock>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PermitNonceResponse {
+    pub nonce: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct UserOfResponse {
+    pub user: Option<String>,
+    pub expires: Option<Expiration>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnerAtResponse {
+    pub owner: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct BalanceAtResponse {
+    pub balance: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HistoryResponse {
+    pub history: Vec<HistoryRecord>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ProvenanceResponse {
+    pub provenance_hash: Option<String>,
+    pub revealed: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TransferableResponse {
+    pub transferable: bool,
+}
+
+/// `address` is empty and `royalty_amount` zero when no royalty applies
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RoyaltyInfoResponse {
+    pub address: String,
+    pub royalty_amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContractInfoResponse {
+    pub name: String,
+    pub symbol: String,
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub external_link: Option<String>,
+    pub base_token_uri: Option<String>,
+    pub admin: Option<String>,
+    pub auction: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct NftInfoResponse {
+    pub name: String,
+    pub token_uri: Option<String>,
+    pub extension: Option<Metadata>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllNftInfoResponse {
+    pub access: OwnerOfResponse,
+    pub info: NftInfoResponse,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokensResponse {
+    pub tokens: Vec<String>,
+}
+
+/// Sent to the receiving contract of a `SendNft`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Cw721ReceiveMsg {
+    pub sender: String,
+    pub token_id: String,
+    pub msg: Binary,
+}
+
+impl Cw721ReceiveMsg {
+    pub fn into_cosmos_msg(self, contract_addr: String) -> StdResult<CosmosMsg> {
+        let msg = to_binary(&ReceiverExecuteMsg::ReceiveNft(self))?;
+        Ok(WasmMsg::Execute {
+            contract_addr,
+            msg,
+            send: vec![],
+        }
+        .into())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum ReceiverExecuteMsg {
+    ReceiveNft(Cw721ReceiveMsg),
 }
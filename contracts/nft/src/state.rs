@@ -1,17 +1,20 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{to_vec, Storage, Uint128};
-use cosmwasm_storage::{bucket, bucket_read, Bucket, ReadonlyBucket};
+use cosmwasm_std::{to_vec, BlockInfo, Storage, Timestamp, Uint128};
+use cosmwasm_storage::{
+    bucket, bucket_read, singleton, singleton_read, Bucket, ReadonlyBucket, ReadonlySingleton,
+    Singleton,
+};
 
 use crate::constant::*;
 
-pub fn operators_resolver(storage: &mut dyn Storage) -> Bucket<bool> {
-    bucket(storage, OPERATORS)
+pub fn operators_resolver<'a>(storage: &'a mut dyn Storage, owner: &str) -> Bucket<'a, Expiration> {
+    Bucket::multilevel(storage, &[OPERATORS, owner.as_bytes()])
 }
 
-pub fn operators_resolver_read(storage: &dyn Storage) -> ReadonlyBucket<bool> {
-    bucket_read(storage, OPERATORS)
+pub fn operators_resolver_read<'a>(storage: &'a dyn Storage, owner: &str) -> ReadonlyBucket<'a, Expiration> {
+    ReadonlyBucket::multilevel(storage, &[OPERATORS, owner.as_bytes()])
 }
 
 pub fn token_resolver(storage: &mut dyn Storage) -> Bucket<Token> {
@@ -22,6 +25,7 @@ pub fn token_resolver_read(storage: &dyn Storage) -> ReadonlyBucket<Token> {
     bucket_read(storage, TOKEN)
 }
 
+/// Legacy per-owner token list, only read by `migrate`.
 pub fn owner_tokens_resolver(storage: &mut dyn Storage) -> Bucket<Vec<TokenId>> {
     bucket(storage, OWNER_TOKENS)
 }
@@ -30,6 +34,22 @@ pub fn owner_tokens_resolver_read(storage: &dyn Storage) -> ReadonlyBucket<Vec<T
     bucket_read(storage, OWNER_TOKENS)
 }
 
+pub fn owner_token_index_resolver<'a>(storage: &'a mut dyn Storage, owner: &str) -> Bucket<'a, TokenId> {
+    Bucket::multilevel(storage, &[OWNER_TOKEN_INDEX, owner.as_bytes()])
+}
+
+pub fn owner_token_index_resolver_read<'a>(storage: &'a dyn Storage, owner: &str) -> ReadonlyBucket<'a, TokenId> {
+    ReadonlyBucket::multilevel(storage, &[OWNER_TOKEN_INDEX, owner.as_bytes()])
+}
+
+pub fn balances_resolver(storage: &mut dyn Storage) -> Bucket<u64> {
+    bucket(storage, BALANCES)
+}
+
+pub fn balances_resolver_read(storage: &dyn Storage) -> ReadonlyBucket<u64> {
+    bucket_read(storage, BALANCES)
+}
+
 pub fn token_owner_resolver(storage: &mut dyn Storage) -> Bucket<String> {
     bucket(storage, TOKEN_OWNER)
 }
@@ -38,6 +58,7 @@ pub fn token_owner_resolver_read(storage: &dyn Storage) -> ReadonlyBucket<String
     bucket_read(storage, TOKEN_OWNER)
 }
 
+/// Legacy single approval per token, only read by `migrate`.
 pub fn token_approvals_resolver(storage: &mut dyn Storage) -> Bucket<String> {
     bucket(storage, TOKEN_APPROVALS)
 }
@@ -46,6 +67,73 @@ pub fn token_approvals_resolver_read(storage: &dyn Storage) -> ReadonlyBucket<St
     bucket_read(storage, TOKEN_APPROVALS)
 }
 
+pub fn token_locks_resolver(storage: &mut dyn Storage) -> Bucket<Lock> {
+    bucket(storage, TOKEN_LOCKS)
+}
+
+pub fn token_locks_resolver_read(storage: &dyn Storage) -> ReadonlyBucket<Lock> {
+    bucket_read(storage, TOKEN_LOCKS)
+}
+
+pub fn token_history_resolver<'a>(storage: &'a mut dyn Storage, token_id: &TokenId) -> Bucket<'a, HistoryRecord> {
+    Bucket::multilevel(storage, &[TOKEN_HISTORY, &token_id.as_bytes()])
+}
+
+pub fn token_history_resolver_read<'a>(storage: &'a dyn Storage, token_id: &TokenId) -> ReadonlyBucket<'a, HistoryRecord> {
+    ReadonlyBucket::multilevel(storage, &[TOKEN_HISTORY, &token_id.as_bytes()])
+}
+
+pub fn token_history_len_resolver(storage: &mut dyn Storage) -> Bucket<u64> {
+    bucket(storage, TOKEN_HISTORY_LEN)
+}
+
+pub fn token_history_len_resolver_read(storage: &dyn Storage) -> ReadonlyBucket<u64> {
+    bucket_read(storage, TOKEN_HISTORY_LEN)
+}
+
+/// Owner of a token after each height it changed at, `None` once burned.
+pub fn owner_checkpoints_resolver<'a>(storage: &'a mut dyn Storage, token_id: &TokenId) -> Bucket<'a, Option<String>> {
+    Bucket::multilevel(storage, &[OWNER_CHECKPOINTS, &token_id.as_bytes()])
+}
+
+pub fn owner_checkpoints_resolver_read<'a>(storage: &'a dyn Storage, token_id: &TokenId) -> ReadonlyBucket<'a, Option<String>> {
+    ReadonlyBucket::multilevel(storage, &[OWNER_CHECKPOINTS, &token_id.as_bytes()])
+}
+
+/// Balance of an owner after each height it changed at.
+pub fn balance_checkpoints_resolver<'a>(storage: &'a mut dyn Storage, owner: &str) -> Bucket<'a, u64> {
+    Bucket::multilevel(storage, &[BALANCE_CHECKPOINTS, owner.as_bytes()])
+}
+
+pub fn balance_checkpoints_resolver_read<'a>(storage: &'a dyn Storage, owner: &str) -> ReadonlyBucket<'a, u64> {
+    ReadonlyBucket::multilevel(storage, &[BALANCE_CHECKPOINTS, owner.as_bytes()])
+}
+
+pub fn token_users_resolver(storage: &mut dyn Storage) -> Bucket<TokenUser> {
+    bucket(storage, TOKEN_USERS)
+}
+
+pub fn token_users_resolver_read(storage: &dyn Storage) -> ReadonlyBucket<TokenUser> {
+    bucket_read(storage, TOKEN_USERS)
+}
+
+pub fn permit_nonces_resolver(storage: &mut dyn Storage) -> Bucket<u64> {
+    bucket(storage, PERMIT_NONCES)
+}
+
+pub fn permit_nonces_resolver_read(storage: &dyn Storage) -> ReadonlyBucket<u64> {
+    bucket_read(storage, PERMIT_NONCES)
+}
+
+pub fn token_spenders_resolver<'a>(storage: &'a mut dyn Storage, token_id: &TokenId) -> Bucket<'a, Expiration> {
+    Bucket::multilevel(storage, &[TOKEN_SPENDERS, &token_id.as_bytes()])
+}
+
+pub fn token_spenders_resolver_read<'a>(storage: &'a dyn Storage, token_id: &TokenId) -> ReadonlyBucket<'a, Expiration> {
+    ReadonlyBucket::multilevel(storage, &[TOKEN_SPENDERS, &token_id.as_bytes()])
+}
+
+/// Legacy list of minted token ids, only read by `migrate`.
 pub fn minted_token_ids_resolver(storage: &mut dyn Storage) -> Bucket<Vec<TokenId>> {
     bucket(storage, MINTED_TOKEN_ID)
 }
@@ -54,10 +142,120 @@ pub fn minted_token_id_resolver_read(storage: &dyn Storage) -> ReadonlyBucket<Ve
     bucket_read(storage, MINTED_TOKEN_ID)
 }
 
+pub fn minter_resolver(storage: &mut dyn Storage) -> Singleton<Minter> {
+    singleton(storage, MINTER)
+}
+
+pub fn minter_resolver_read(storage: &dyn Storage) -> ReadonlySingleton<Minter> {
+    singleton_read(storage, MINTER)
+}
+
+pub fn next_token_id_resolver(storage: &mut dyn Storage) -> Singleton<Uint128> {
+    singleton(storage, NEXT_TOKEN_ID)
+}
+
+pub fn next_token_id_resolver_read(storage: &dyn Storage) -> ReadonlySingleton<Uint128> {
+    singleton_read(storage, NEXT_TOKEN_ID)
+}
+
+/// Progress of the paged token scan of `migrate`.
+pub fn token_scan_resolver(storage: &mut dyn Storage) -> Singleton<TokenScan> {
+    singleton(storage, MIGRATE_TOKEN_SCAN)
+}
+
+pub fn token_scan_resolver_read(storage: &dyn Storage) -> ReadonlySingleton<TokenScan> {
+    singleton_read(storage, MIGRATE_TOKEN_SCAN)
+}
+
+pub fn num_tokens_resolver(storage: &mut dyn Storage) -> Singleton<u64> {
+    singleton(storage, NUM_TOKENS)
+}
+
+pub fn num_tokens_resolver_read(storage: &dyn Storage) -> ReadonlySingleton<u64> {
+    singleton_read(storage, NUM_TOKENS)
+}
+
+/// Tokens ever minted, burned tokens included. `max_supply` caps this count.
+pub fn minted_count_resolver(storage: &mut dyn Storage) -> Singleton<u64> {
+    singleton(storage, MINTED_COUNT)
+}
+
+pub fn minted_count_resolver_read(storage: &dyn Storage) -> ReadonlySingleton<u64> {
+    singleton_read(storage, MINTED_COUNT)
+}
+
+pub fn royalty_resolver(storage: &mut dyn Storage) -> Singleton<Royalty> {
+    singleton(storage, ROYALTY)
+}
+
+pub fn royalty_resolver_read(storage: &dyn Storage) -> ReadonlySingleton<Royalty> {
+    singleton_read(storage, ROYALTY)
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
     pub name: String,
     pub symbol: String,
+    #[serde(default)]
+    pub token_id_mode: TokenIdMode,
+    /// default for tokens minted without their own flag
+    #[serde(default = "default_transferable")]
+    pub transferable: bool,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub image: Option<String>,
+    #[serde(default)]
+    pub external_link: Option<String>,
+    /// token uris of tokens minted without their own uri are `base_token_uri + token_id`
+    #[serde(default)]
+    pub base_token_uri: Option<String>,
+    /// may update the collection info, `migrate` sets it to the minter for collections instantiated without one
+    #[serde(default)]
+    pub admin: Option<String>,
+    /// shown as the uri of every token until the collection is revealed
+    #[serde(default)]
+    pub placeholder_uri: Option<String>,
+    /// commitment to the final metadata, published before the reveal
+    #[serde(default)]
+    pub provenance_hash: Option<String>,
+    #[serde(default)]
+    pub revealed: bool,
+    /// keeps ownership and balance checkpoints for the `OwnerAt` and `BalanceAt` queries
+    #[serde(default)]
+    pub snapshots: bool,
+    /// trusted auction contract, the only caller of the dynamic link callable points
+    #[serde(default)]
+    pub auction: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenIdMode {
+    /// the contract assigns 0, 1, 2, ...
+    Sequential,
+    /// minters supply a unique string id for every token
+    Custom,
+}
+
+impl Default for TokenIdMode {
+    fn default() -> Self {
+        TokenIdMode::Sequential
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenScan {
+    /// key of the last scanned token
+    pub start_after: Option<Vec<u8>>,
+    pub last_token_id: Option<Uint128>,
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Minter {
+    pub minter: String,
+    pub max_supply: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -65,32 +263,75 @@ pub struct Token {
     pub token_id: TokenId,
     pub name: String,
     pub uri: String,
+    #[serde(default)]
+    pub extension: Option<Metadata>,
+    /// once set, the extension can no longer be updated
+    #[serde(default)]
+    pub metadata_frozen: bool,
+    /// overrides the collection royalty
+    #[serde(default)]
+    pub royalty: Option<Royalty>,
+    /// soulbound tokens cannot be transferred or approved, only burned by their owner
+    #[serde(default = "default_transferable")]
+    pub transferable: bool,
 }
 
 impl Token {
-    pub fn new(token_id: TokenId, name: String, uri: String) -> Self {
-        Token {token_id, name, uri}
+    pub fn new(
+        token_id: TokenId,
+        name: String,
+        uri: String,
+        extension: Option<Metadata>,
+        royalty: Option<Royalty>,
+        transferable: bool,
+    ) -> Self {
+        Token {token_id, name, uri, extension, metadata_frozen: false, royalty, transferable}
     }
 }
 
+fn default_transferable() -> bool {
+    true
+}
+
+/// Creator fee paid to `payment_address` on every sale, in basis points of the sale price.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Royalty {
+    pub payment_address: String,
+    pub royalty_bps: u64,
+}
+
+/// On-chain metadata following the common erc721 / opensea metadata fields.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Metadata {
+    pub description: Option<String>,
+    pub image: Option<String>,
+    pub external_url: Option<String>,
+    pub attributes: Option<Vec<Trait>>,
+    pub animation_url: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct TokenId(Uint128);
+pub struct Trait {
+    pub trait_type: String,
+    pub value: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenId(String);
 
 impl TokenId {
-    pub fn new(v: Uint128) -> Self {
+    pub fn new(v: String) -> Self {
         TokenId(v)
     }
 
-    pub fn as_u128(&self) -> Uint128 {
-        self.0
-    }
-
+    /// Storage key of the token. Numeric ids serialize exactly like the former
+    /// `Uint128` ids, so existing collections keep their keys.
     pub fn as_bytes(&self) -> Vec<u8> {
         to_vec(&self.0).unwrap()
     }
 
     pub fn as_string(&self) -> String {
-        self.0.to_string()
+        self.0.clone()
     }
 
     pub fn equal(&self, other: &Self) -> bool {
@@ -98,8 +339,67 @@ impl TokenId {
     }
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct OperatorKey {
-    pub sender: String,
-    pub operator: String
+/// One change of ownership, stored under its big-endian `index` per token.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HistoryRecord {
+    pub index: u64,
+    pub owner: String,
+    pub height: u64,
+    pub time: Timestamp,
+    pub transfer_type: TransferType,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferType {
+    Mint,
+    Transfer,
+    /// a transfer flagged as the settlement of a sale
+    Sale,
+}
+
+/// Address allowed to use a token without owning it, erc4907 style.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenUser {
+    pub user: String,
+    pub expires: Expiration,
+}
+
+/// A locked token cannot be transferred or burned until `unlock_authority` unlocks it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Lock {
+    pub locked_by: String,
+    pub reason: String,
+    pub unlock_authority: String,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Approval {
+    pub spender: String,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Expiration {
+    AtHeight(u64),
+    AtTime(Timestamp),
+    Never {},
+}
+
+impl Expiration {
+    pub fn is_expired(&self, block: &BlockInfo) -> bool {
+        match self {
+            Expiration::AtHeight(height) => block.height >= *height,
+            Expiration::AtTime(time) => block.time.nanos() >= time.nanos(),
+            Expiration::Never {} => false,
+        }
+    }
+}
+
+impl Default for Expiration {
+    fn default() -> Self {
+        Expiration::Never {}
+    }
+}
+
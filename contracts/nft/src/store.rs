@@ -1,4 +1,7 @@
-use cosmwasm_std::{StdResult, Storage};
+use cosmwasm_std::{from_slice, to_vec, BlockInfo, Order, StdError, StdResult, Storage, Uint128};
+use cosmwasm_storage::{PrefixedStorage, ReadonlyPrefixedStorage};
+
+use crate::constant::*;
 
 use crate::state::*;
 
@@ -6,10 +9,8 @@ pub fn read_operators_store(
     store: &dyn Storage,
     sender: String,
     operator: String,
-) -> StdResult<Option<bool>> {
-    let ok = OperatorKey {sender, operator};
-    let key = bincode::serialize(&ok).unwrap();
-    let res = operators_resolver_read(store).may_load(&key)?;
+) -> StdResult<Option<Expiration>> {
+    let res = operators_resolver_read(store, &sender).may_load(operator.as_bytes())?;
     Ok(res)
 }
 
@@ -17,11 +18,57 @@ pub fn write_operators_store(
     store: &mut dyn Storage,
     sender: String,
     operator: String,
-    approved: bool,
+    expires: Expiration,
+) -> StdResult<()> {
+    operators_resolver(store, &sender).save(operator.as_bytes(), &expires)?;
+    Ok(())
+}
+
+pub fn remove_operators_store(
+    store: &mut dyn Storage,
+    sender: String,
+    operator: String,
+) {
+    operators_resolver(store, &sender).remove(operator.as_bytes());
+}
+
+/// Operators of the first release, `true`/`false` under the bincode encoded
+/// (sender, operator) pair. They share the `OPERATORS` namespace with the
+/// per-owner operators, whose values are never booleans.
+pub fn read_legacy_operators_store(
+    store: &dyn Storage,
+    limit: u32,
+) -> Vec<(Vec<u8>, bool)> {
+    ReadonlyPrefixedStorage::new(store, OPERATORS)
+        .range(None, None, Order::Ascending)
+        .filter_map(|(key, value)| match value.as_slice() {
+            b"true" => Some((key, true)),
+            b"false" => Some((key, false)),
+            _ => None,
+        })
+        .take(limit as usize)
+        .collect()
+}
+
+pub fn remove_legacy_operator_store(
+    store: &mut dyn Storage,
+    key: &[u8],
+) {
+    PrefixedStorage::new(store, OPERATORS).remove(key);
+}
+
+pub fn read_state_store(store: &dyn Storage) -> StdResult<State> {
+    let config_store = ReadonlyPrefixedStorage::new(store, CONFIG);
+    let state = config_store.get(KEY_STATE).ok_or_else(|| StdError::not_found("state"))?;
+    from_slice(&state)
+}
+
+pub fn write_state_store(
+    store: &mut dyn Storage,
+    state: State,
 ) -> StdResult<()> {
-    let ok = OperatorKey {sender, operator};
-    let key = bincode::serialize(&ok).unwrap();
-    operators_resolver(store).save(&key, &approved)?;
+    let mut config_store = PrefixedStorage::new(store, CONFIG);
+    config_store.set(KEY_STATE, &to_vec(&state)?);
     Ok(())
 }
 
@@ -42,29 +89,63 @@ pub fn write_token_store(
     Ok(())
 }
 
-pub fn read_owner_tokens_store(
-    store: &dyn Storage,
+pub fn remove_token_store(
+    store: &mut dyn Storage,
+    token_id: TokenId,
+) {
+    token_resolver(store).remove(&token_id.as_bytes());
+}
+
+pub fn add_owner_token_store(
+    store: &mut dyn Storage,
     owner: String,
-) -> StdResult<Vec<TokenId>> {
-    let token_list = match owner_tokens_resolver_read(store).may_load(&owner.as_bytes())? {
-        Some(record) => record,
-        None => {
-            let v: Vec<TokenId> = vec![];
-            v
-        }
-    };
-    Ok(token_list)
+    token_id: TokenId,
+) -> StdResult<()> {
+    add_owner_tokens_store(store, owner, &[token_id])
+}
+
+pub fn remove_owner_token_store(
+    store: &mut dyn Storage,
+    owner: String,
+    token_id: TokenId,
+) -> StdResult<()> {
+    remove_owner_tokens_store(store, owner, &[token_id])
+}
+
+/// Indexes `token_ids` under `owner` and bumps the balance with a single write.
+pub fn add_owner_tokens_store(
+    store: &mut dyn Storage,
+    owner: String,
+    token_ids: &[TokenId],
+) -> StdResult<()> {
+    for token_id in token_ids {
+        owner_token_index_resolver(store, &owner).save(&token_id.as_bytes(), token_id)?;
+    }
+    let balance = read_balance_store(store, owner.clone())?;
+    balances_resolver(store).save(owner.as_bytes(), &(balance + token_ids.len() as u64))?;
+    Ok(())
 }
 
-pub fn write_owner_tokens_store(
+pub fn remove_owner_tokens_store(
     store: &mut dyn Storage,
     owner: String,
-    token_id_set: Vec<TokenId>,
+    token_ids: &[TokenId],
 ) -> StdResult<()> {
-    owner_tokens_resolver(store).save(owner.as_bytes(), &token_id_set)?;
+    for token_id in token_ids {
+        owner_token_index_resolver(store, &owner).remove(&token_id.as_bytes());
+    }
+    let balance = read_balance_store(store, owner.clone())?;
+    balances_resolver(store).save(owner.as_bytes(), &balance.saturating_sub(token_ids.len() as u64))?;
     Ok(())
 }
 
+pub fn read_balance_store(
+    store: &dyn Storage,
+    owner: String,
+) -> StdResult<u64> {
+    Ok(balances_resolver_read(store).may_load(owner.as_bytes())?.unwrap_or(0))
+}
+
 pub fn read_token_owner_store(
     store: &dyn Storage,
     token_id: TokenId,
@@ -81,30 +162,248 @@ pub fn write_token_owner_store(
     Ok(())
 }
 
-pub fn read_minted_token_id_store(store: &dyn Storage) -> StdResult<Option<Vec<TokenId>>> {
-    minted_token_id_resolver_read(store).may_load(b"minter")
+pub fn remove_token_owner_store(
+    store: &mut dyn Storage,
+    token_id: TokenId,
+) {
+    token_owner_resolver(store).remove(&token_id.as_bytes());
+}
+
+pub fn read_next_token_id_store(store: &dyn Storage) -> StdResult<Uint128> {
+    Ok(next_token_id_resolver_read(store).may_load()?.unwrap_or_else(Uint128::zero))
+}
+
+pub fn write_next_token_id_store(
+    store: &mut dyn Storage,
+    next_token_id: Uint128,
+) -> StdResult<()> {
+    next_token_id_resolver(store).save(&next_token_id)?;
+    Ok(())
+}
+
+pub fn read_token_approval_store(
+    store: &dyn Storage,
+    token_id: TokenId,
+    spender: String,
+) -> StdResult<Option<Expiration>> {
+    token_spenders_resolver_read(store, &token_id).may_load(spender.as_bytes())
 }
 
-pub fn write_minted_token_id_store(
+pub fn write_token_approval_store(
     store: &mut dyn Storage,
-    token_id_set: Vec<TokenId>,
+    token_id: TokenId,
+    spender: String,
+    expires: Expiration,
 ) -> StdResult<()> {
-    minted_token_ids_resolver(store).save(b"minter", &token_id_set)?;
+    token_spenders_resolver(store, &token_id).save(spender.as_bytes(), &expires)?;
     Ok(())
 }
 
+pub fn remove_token_approval_store(
+    store: &mut dyn Storage,
+    token_id: TokenId,
+    spender: String,
+) {
+    token_spenders_resolver(store, &token_id).remove(spender.as_bytes());
+}
+
 pub fn read_token_approvals_store(
     store: &dyn Storage,
     token_id: TokenId,
+) -> StdResult<Vec<Approval>> {
+    token_spenders_resolver_read(store, &token_id)
+        .range(None, None, Order::Ascending)
+        .map(|item| {
+            let (key, expires) = item?;
+            let spender = String::from_utf8(key).map_err(StdError::invalid_utf8)?;
+            Ok(Approval { spender, expires })
+        })
+        .collect()
+}
+
+pub fn remove_token_approvals_store(
+    store: &mut dyn Storage,
+    token_id: TokenId,
+) -> StdResult<()> {
+    for approval in read_token_approvals_store(store, token_id.clone())? {
+        remove_token_approval_store(store, token_id.clone(), approval.spender);
+    }
+    Ok(())
+}
+
+pub fn read_token_lock_store(
+    store: &dyn Storage,
+    token_id: TokenId,
+) -> StdResult<Option<Lock>> {
+    token_locks_resolver_read(store).may_load(&token_id.as_bytes())
+}
+
+pub fn write_token_lock_store(
+    store: &mut dyn Storage,
+    token_id: TokenId,
+    lock: Lock,
+) -> StdResult<()> {
+    token_locks_resolver(store).save(&token_id.as_bytes(), &lock)?;
+    Ok(())
+}
+
+pub fn remove_token_lock_store(
+    store: &mut dyn Storage,
+    token_id: TokenId,
+) {
+    token_locks_resolver(store).remove(&token_id.as_bytes());
+}
+
+/// Appends a record with the next index of the token.
+pub fn add_token_history_store(
+    store: &mut dyn Storage,
+    token_id: TokenId,
+    owner: String,
+    block: &BlockInfo,
+    transfer_type: TransferType,
+) -> StdResult<()> {
+    let index = token_history_len_resolver_read(store).may_load(&token_id.as_bytes())?.unwrap_or(0);
+    let record = HistoryRecord {
+        index,
+        owner,
+        height: block.height,
+        time: block.time,
+        transfer_type,
+    };
+    token_history_resolver(store, &token_id).save(&index.to_be_bytes(), &record)?;
+    token_history_len_resolver(store).save(&token_id.as_bytes(), &(index + 1))?;
+    Ok(())
+}
+
+pub fn write_owner_checkpoint_store(
+    store: &mut dyn Storage,
+    token_id: TokenId,
+    height: u64,
+    owner: Option<String>,
+) -> StdResult<()> {
+    owner_checkpoints_resolver(store, &token_id).save(&height.to_be_bytes(), &owner)?;
+    Ok(())
+}
+
+/// Owner after the block at `height`, from the last checkpoint at or below it.
+pub fn read_owner_at_store(
+    store: &dyn Storage,
+    token_id: TokenId,
+    height: u64,
 ) -> StdResult<Option<String>> {
-    token_approvals_resolver_read(store).may_load(&token_id.as_bytes())
+    let end = height.saturating_add(1).to_be_bytes();
+    match owner_checkpoints_resolver_read(store, &token_id).range(None, Some(&end), Order::Descending).next() {
+        Some(item) => Ok(item?.1),
+        None => Ok(None),
+    }
+}
+
+pub fn write_balance_checkpoint_store(
+    store: &mut dyn Storage,
+    owner: String,
+    height: u64,
+    balance: u64,
+) -> StdResult<()> {
+    balance_checkpoints_resolver(store, &owner).save(&height.to_be_bytes(), &balance)?;
+    Ok(())
+}
+
+/// Balance after the block at `height`, from the last checkpoint at or below it.
+pub fn read_balance_at_store(
+    store: &dyn Storage,
+    owner: String,
+    height: u64,
+) -> StdResult<u64> {
+    let end = height.saturating_add(1).to_be_bytes();
+    match balance_checkpoints_resolver_read(store, &owner).range(None, Some(&end), Order::Descending).next() {
+        Some(item) => Ok(item?.1),
+        None => Ok(0),
+    }
+}
+
+pub fn read_token_user_store(
+    store: &dyn Storage,
+    token_id: TokenId,
+) -> StdResult<Option<TokenUser>> {
+    token_users_resolver_read(store).may_load(&token_id.as_bytes())
+}
+
+pub fn write_token_user_store(
+    store: &mut dyn Storage,
+    token_id: TokenId,
+    token_user: TokenUser,
+) -> StdResult<()> {
+    token_users_resolver(store).save(&token_id.as_bytes(), &token_user)?;
+    Ok(())
 }
 
-pub fn write_token_approvals_store(
+pub fn remove_token_user_store(
     store: &mut dyn Storage,
     token_id: TokenId,
-    addr: String,
+) {
+    token_users_resolver(store).remove(&token_id.as_bytes());
+}
+
+pub fn read_permit_nonce_store(
+    store: &dyn Storage,
+    owner: String,
+) -> StdResult<u64> {
+    Ok(permit_nonces_resolver_read(store).may_load(owner.as_bytes())?.unwrap_or(0))
+}
+
+pub fn write_permit_nonce_store(
+    store: &mut dyn Storage,
+    owner: String,
+    nonce: u64,
+) -> StdResult<()> {
+    permit_nonces_resolver(store).save(owner.as_bytes(), &nonce)?;
+    Ok(())
+}
+
+pub fn read_minter_store(store: &dyn Storage) -> StdResult<Option<Minter>> {
+    minter_resolver_read(store).may_load()
+}
+
+pub fn write_minter_store(
+    store: &mut dyn Storage,
+    minter: Minter,
+) -> StdResult<()> {
+    minter_resolver(store).save(&minter)?;
+    Ok(())
+}
+
+pub fn read_royalty_store(store: &dyn Storage) -> StdResult<Option<Royalty>> {
+    royalty_resolver_read(store).may_load()
+}
+
+pub fn write_royalty_store(
+    store: &mut dyn Storage,
+    royalty: Royalty,
+) -> StdResult<()> {
+    royalty_resolver(store).save(&royalty)?;
+    Ok(())
+}
+
+pub fn read_num_tokens_store(store: &dyn Storage) -> StdResult<u64> {
+    Ok(num_tokens_resolver_read(store).may_load()?.unwrap_or(0))
+}
+
+pub fn write_num_tokens_store(
+    store: &mut dyn Storage,
+    num_tokens: u64,
+) -> StdResult<()> {
+    num_tokens_resolver(store).save(&num_tokens)?;
+    Ok(())
+}
+
+pub fn read_minted_count_store(store: &dyn Storage) -> StdResult<u64> {
+    Ok(minted_count_resolver_read(store).may_load()?.unwrap_or(0))
+}
+
+pub fn write_minted_count_store(
+    store: &mut dyn Storage,
+    minted_count: u64,
 ) -> StdResult<()> {
-    token_approvals_resolver(store).save(&token_id.as_bytes(), &addr)?;
+    minted_count_resolver(store).save(&minted_count)?;
     Ok(())
 }
@@ -1 +1,934 @@
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage};
+use cosmwasm_std::{from_binary, Binary, Env, Order, OwnedDeps, Storage, Uint128};
+use cosmwasm_storage::{bucket, PrefixedStorage};
+use k256::ecdsa::signature::Signer;
+use k256::ecdsa::{Signature, SigningKey};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 
+use crate::constant::*;
+use crate::contract::{
+    apply_permit, execute, instantiate, linked_approve, linked_transfer, linked_transfer_from, migrate, permit_signer,
+    query,
+};
+use crate::errors::ContractError;
+use crate::msg::{
+    ApprovalsResponse, BalanceAtResponse, ContractInfoResponse, ExecuteMsg, HistoryResponse, InstantiateMsg,
+    IsApprovedForAllResponse, MigrateMsg, MintMsg, MinterResponse, NftInfoResponse, NumTokensResponse, OwnerAtResponse,
+    OwnerOfResponse, PermitMsg, PermitNonceResponse, QueryMsg, RoyaltyInfoResponse, TokensResponse,
+    TransferableResponse, UserOfResponse,
+};
+use crate::state::*;
+use crate::store::*;
+
+const CREATOR: &str = "creator";
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+const AUCTION: &str = "auction";
+
+type MockDeps = OwnedDeps<MockStorage, MockApi, MockQuerier>;
+
+/// Token as stored by the contract before the migration.
+#[derive(Serialize)]
+struct LegacyToken {
+    token_id: String,
+    name: String,
+    uri: String,
+}
+
+fn default_instantiate_msg() -> InstantiateMsg {
+    InstantiateMsg {
+        name: "collection".to_string(),
+        symbol: "COL".to_string(),
+        minter: None,
+        max_supply: None,
+        token_id_mode: None,
+        royalty: None,
+        transferable: None,
+        description: None,
+        image: None,
+        external_link: None,
+        base_token_uri: None,
+        admin: None,
+        placeholder_uri: None,
+        provenance_hash: None,
+        snapshots: None,
+        auction: None,
+    }
+}
+
+fn setup_contract(msg: InstantiateMsg) -> MockDeps {
+    let mut deps = mock_dependencies(&[]);
+    instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), msg).unwrap();
+    deps
+}
+
+fn mint_msg(owner: &str) -> MintMsg {
+    MintMsg {
+        owner: Some(owner.to_string()),
+        token_id: None,
+        name: "token".to_string(),
+        uri: "token.example.com".to_string(),
+        extension: None,
+        royalty: None,
+        transferable: None,
+    }
+}
+
+fn env_at(height: u64) -> Env {
+    let mut env = mock_env();
+    env.block.height = height;
+    env
+}
+
+fn mint(deps: &mut MockDeps, owner: &str) -> String {
+    let res = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), ExecuteMsg::Mint(mint_msg(owner))).unwrap();
+    res.attributes.iter().find(|attr| attr.key == "token_id").unwrap().value.clone()
+}
+
+fn migrate_msg() -> MigrateMsg {
+    MigrateMsg {
+        minter: Some(CREATOR.to_string()),
+        max_supply: None,
+        limit: None,
+    }
+}
+
+/// bincode encoding of the legacy (sender, operator) key.
+fn legacy_operator_key(sender: &str, operator: &str) -> Vec<u8> {
+    let mut key = vec![];
+    for value in &[sender, operator] {
+        key.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        key.extend_from_slice(value.as_bytes());
+    }
+    key
+}
+
+/// Writes a collection in the storage layout of the first release: the state
+/// without the later fields, one token owned by alice and approved to bob,
+/// and bob as an operator of alice.
+fn setup_legacy_contract() -> MockDeps {
+    let mut deps = mock_dependencies(&[]);
+
+    PrefixedStorage::new(&mut deps.storage, CONFIG)
+        .set(KEY_STATE, br#"{"name":"legacy","symbol":"LGC"}"#);
+
+    let token_id = TokenId::new("1".to_string());
+    bucket(&mut deps.storage, TOKEN)
+        .save(&token_id.as_bytes(), &LegacyToken { token_id: "1".to_string(), name: "token".to_string(), uri: "token.example.com".to_string() })
+        .unwrap();
+    token_owner_resolver(&mut deps.storage).save(&token_id.as_bytes(), &ALICE.to_string()).unwrap();
+    owner_tokens_resolver(&mut deps.storage).save(ALICE.as_bytes(), &vec![token_id.clone()]).unwrap();
+    minted_token_ids_resolver(&mut deps.storage).save(b"minter", &vec![token_id.clone()]).unwrap();
+    token_approvals_resolver(&mut deps.storage).save(&token_id.as_bytes(), &BOB.to_string()).unwrap();
+    PrefixedStorage::new(&mut deps.storage, OPERATORS).set(&legacy_operator_key(ALICE, BOB), b"true");
+    PrefixedStorage::new(&mut deps.storage, OPERATORS).set(&legacy_operator_key(BOB, ALICE), b"false");
+
+    deps
+}
+
+#[test]
+fn migrate_converts_legacy_approvals() {
+    let mut deps = setup_legacy_contract();
+
+    migrate(deps.as_mut(), mock_env(), migrate_msg()).unwrap();
+
+    let token_id = TokenId::new("1".to_string());
+    assert_eq!(read_token_approval_store(&deps.storage, token_id.clone(), BOB.to_string()).unwrap(), Some(Expiration::Never {}));
+    assert_eq!(token_approvals_resolver_read(&deps.storage).may_load(&token_id.as_bytes()).unwrap(), None);
+
+    let res: ApprovalsResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Approvals {
+        token_id: "1".to_string(),
+        include_expired: None,
+    }).unwrap()).unwrap();
+    assert_eq!(res.approvals, vec![Approval { spender: BOB.to_string(), expires: Expiration::Never {} }]);
+
+    // the migrated approval lets bob move the token
+    execute(deps.as_mut(), mock_env(), mock_info(BOB, &[]), ExecuteMsg::TransferFrom {
+        sender: ALICE.to_string(),
+        recipient: BOB.to_string(),
+        token_id: "1".to_string(),
+    }).unwrap();
+    assert_eq!(read_token_owner_store(&deps.storage, token_id).unwrap(), Some(BOB.to_string()));
+}
+
+#[test]
+fn migrate_seeds_minter_and_num_tokens() {
+    let mut deps = setup_legacy_contract();
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg { minter: Some(CREATOR.to_string()), max_supply: Some(2), limit: None }).unwrap();
+
+    let res: MinterResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Minter {}).unwrap()).unwrap();
+    assert_eq!(res, MinterResponse { minter: CREATOR.to_string(), max_supply: Some(2) });
+    let res: NumTokensResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::NumTokens {}).unwrap()).unwrap();
+    assert_eq!(res.count, 1);
+
+    // minting continues after the legacy token and stops at the max supply
+    assert_eq!(mint(&mut deps, ALICE), "2");
+    let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), ExecuteMsg::Mint(mint_msg(ALICE))).unwrap_err();
+    assert!(matches!(err, ContractError::MaxSupplyReached {}));
+
+    // a second migration keeps the minter and the supply
+    migrate(deps.as_mut(), mock_env(), MigrateMsg { minter: Some(BOB.to_string()), max_supply: None, limit: None }).unwrap();
+    assert_eq!(read_minter_store(&deps.storage).unwrap().unwrap().minter, CREATOR);
+    assert_eq!(read_num_tokens_store(&deps.storage).unwrap(), 2);
+}
+
+#[test]
+fn migrate_requires_minter_for_legacy_collections() {
+    let mut deps = setup_legacy_contract();
+
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg { minter: None, max_supply: None, limit: None }).unwrap_err();
+    assert!(matches!(err, ContractError::MinterRequired {}));
+}
+
+#[test]
+fn migrate_seeds_admin_for_legacy_collections() {
+    let mut deps = setup_legacy_contract();
+
+    migrate(deps.as_mut(), mock_env(), migrate_msg()).unwrap();
+
+    let res: ContractInfoResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::ContractInfo {}).unwrap()).unwrap();
+    assert_eq!(res.admin, Some(CREATOR.to_string()));
+
+    let update = ExecuteMsg::UpdateCollectionInfo {
+        description: Some("legacy collection".to_string()),
+        image: None,
+        external_link: None,
+        base_token_uri: None,
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(BOB, &[]), update.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), update).unwrap();
+    assert_eq!(read_state_store(&deps.storage).unwrap().description, Some("legacy collection".to_string()));
+}
+
+#[test]
+fn migrate_moves_owner_tokens_in_pages() {
+    let mut deps = setup_legacy_contract();
+    let token_id = TokenId::new("2".to_string());
+    bucket(&mut deps.storage, TOKEN)
+        .save(&token_id.as_bytes(), &LegacyToken { token_id: "2".to_string(), name: "token".to_string(), uri: "token.example.com".to_string() })
+        .unwrap();
+    token_owner_resolver(&mut deps.storage).save(&token_id.as_bytes(), &BOB.to_string()).unwrap();
+    owner_tokens_resolver(&mut deps.storage).save(BOB.as_bytes(), &vec![token_id]).unwrap();
+
+    let msg = MigrateMsg { minter: Some(CREATOR.to_string()), max_supply: None, limit: Some(1) };
+    let res = migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+    assert_eq!(res.attributes[1].value, "false");
+    assert_eq!(read_balance_store(&deps.storage, ALICE.to_string()).unwrap(), 1);
+    assert_eq!(read_balance_store(&deps.storage, BOB.to_string()).unwrap(), 0);
+
+    let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(res.attributes[1].value, "true");
+    assert_eq!(read_balance_store(&deps.storage, BOB.to_string()).unwrap(), 1);
+    assert_eq!(read_num_tokens_store(&deps.storage).unwrap(), 2);
+}
+
+#[test]
+fn migrate_scans_tokens_in_pages() {
+    let mut deps = setup_legacy_contract();
+    // the legacy minted list only kept the tokens of the last mint call
+    for token_id in &["5", "3"] {
+        bucket(&mut deps.storage, TOKEN)
+            .save(token_id.as_bytes(), &LegacyToken { token_id: token_id.to_string(), name: "token".to_string(), uri: "token.example.com".to_string() })
+            .unwrap();
+    }
+
+    let msg = MigrateMsg { minter: Some(CREATOR.to_string()), max_supply: None, limit: Some(2) };
+    let res = migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+    assert_eq!(res.attributes[2].value, "false");
+    assert!(minted_token_id_resolver_read(&deps.storage).may_load(b"minter").unwrap().is_some());
+    assert!(num_tokens_resolver_read(&deps.storage).may_load().unwrap().is_none());
+
+    let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(res.attributes[2].value, "true");
+    assert!(minted_token_id_resolver_read(&deps.storage).may_load(b"minter").unwrap().is_none());
+    assert!(token_scan_resolver_read(&deps.storage).may_load().unwrap().is_none());
+    assert_eq!(read_num_tokens_store(&deps.storage).unwrap(), 3);
+    assert_eq!(read_minted_count_store(&deps.storage).unwrap(), 3);
+    assert_eq!(mint(&mut deps, ALICE), "6");
+}
+
+#[test]
+fn migrate_moves_legacy_operators() {
+    let mut deps = setup_legacy_contract();
+    write_operators_store(&mut deps.storage, CREATOR.to_string(), BOB.to_string(), Expiration::AtHeight(100)).unwrap();
+
+    migrate(deps.as_mut(), mock_env(), migrate_msg()).unwrap();
+
+    assert_eq!(read_operators_store(&deps.storage, ALICE.to_string(), BOB.to_string()).unwrap(), Some(Expiration::Never {}));
+    assert_eq!(read_operators_store(&deps.storage, BOB.to_string(), ALICE.to_string()).unwrap(), None);
+    assert_eq!(read_operators_store(&deps.storage, CREATOR.to_string(), BOB.to_string()).unwrap(), Some(Expiration::AtHeight(100)));
+    assert!(read_legacy_operators_store(&deps.storage, 10).is_empty());
+
+    let res: IsApprovedForAllResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::IsApprovedForAll {
+        owner: ALICE.to_string(),
+        operator: BOB.to_string(),
+    }).unwrap()).unwrap();
+    assert!(res.approved);
+}
+
+#[test]
+fn migrate_moves_legacy_approvals_and_operators_in_pages() {
+    let mut deps = setup_legacy_contract();
+    let token_id = TokenId::new("2".to_string());
+    token_approvals_resolver(&mut deps.storage).save(&token_id.as_bytes(), &ALICE.to_string()).unwrap();
+
+    let msg = MigrateMsg { minter: Some(CREATOR.to_string()), max_supply: None, limit: Some(1) };
+    let res = migrate(deps.as_mut(), mock_env(), msg.clone()).unwrap();
+    assert_eq!(res.attributes[3].value, "false");
+    assert_eq!(res.attributes[4].value, "false");
+    assert_eq!(read_legacy_operators_store(&deps.storage, 10).len(), 1);
+
+    let res = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+    assert_eq!(res.attributes[3].value, "true");
+    assert_eq!(res.attributes[4].value, "true");
+    assert_eq!(read_token_approval_store(&deps.storage, token_id, ALICE.to_string()).unwrap(), Some(Expiration::Never {}));
+    assert_eq!(read_operators_store(&deps.storage, ALICE.to_string(), BOB.to_string()).unwrap(), Some(Expiration::Never {}));
+}
+
+/// Collection trusting the auction, with a token of alice, who made the auction an operator.
+fn setup_auction_contract() -> (MockDeps, String) {
+    let mut deps = setup_contract(InstantiateMsg { auction: Some(AUCTION.to_string()), ..default_instantiate_msg() });
+    let token_id = mint(&mut deps, ALICE);
+    execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), ExecuteMsg::ApproveForAll {
+        opeartor: AUCTION.to_string(),
+        approved: true,
+        expires: None,
+    }).unwrap();
+    (deps, token_id)
+}
+
+#[test]
+fn linked_calls_match_messages() {
+    // escrow and settle the way the auction does with messages
+    let (mut message_deps, token_id) = setup_auction_contract();
+    let auction_info = mock_info(AUCTION, &[]);
+    execute(message_deps.as_mut(), mock_env(), auction_info.clone(), ExecuteMsg::Approve {
+        recipient: AUCTION.to_string(),
+        token_id: token_id.clone(),
+        expires: None,
+    }).unwrap();
+    execute(message_deps.as_mut(), mock_env(), auction_info.clone(), ExecuteMsg::TransferFrom {
+        sender: ALICE.to_string(),
+        recipient: AUCTION.to_string(),
+        token_id: token_id.clone(),
+    }).unwrap();
+    execute(message_deps.as_mut(), mock_env(), auction_info, ExecuteMsg::Transfer {
+        recipient: BOB.to_string(),
+        token_id: token_id.clone(),
+        sale: Some(true),
+    }).unwrap();
+
+    // and through the dynamic link
+    let (mut linked_deps, token_id) = setup_auction_contract();
+    linked_approve(linked_deps.as_mut(), mock_env(), AUCTION.to_string(), AUCTION.to_string(), token_id.clone()).unwrap();
+    linked_transfer_from(linked_deps.as_mut(), mock_env(), AUCTION.to_string(), ALICE.to_string(), AUCTION.to_string(), token_id.clone()).unwrap();
+    linked_transfer(linked_deps.as_mut(), mock_env(), AUCTION.to_string(), BOB.to_string(), token_id.clone(), true).unwrap();
+
+    assert_eq!(read_token_owner_store(&linked_deps.storage, TokenId::new(token_id)).unwrap(), Some(BOB.to_string()));
+    assert_eq!(
+        message_deps.storage.range(None, None, Order::Ascending).collect::<Vec<_>>(),
+        linked_deps.storage.range(None, None, Order::Ascending).collect::<Vec<_>>(),
+    );
+}
+
+#[test]
+fn linked_calls_need_the_trusted_auction() {
+    let (mut deps, token_id) = setup_auction_contract();
+    execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), ExecuteMsg::ApproveForAll {
+        opeartor: BOB.to_string(),
+        approved: true,
+        expires: None,
+    }).unwrap();
+
+    // an operator of alice is still not the auction
+    let err = linked_transfer_from(deps.as_mut(), mock_env(), BOB.to_string(), ALICE.to_string(), BOB.to_string(), token_id.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let err = linked_approve(deps.as_mut(), mock_env(), BOB.to_string(), BOB.to_string(), token_id.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    // the admin can withdraw the trust
+    let err = execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), ExecuteMsg::SetAuction { auction: None }).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), ExecuteMsg::SetAuction { auction: None }).unwrap();
+    let err = linked_transfer_from(deps.as_mut(), mock_env(), AUCTION.to_string(), ALICE.to_string(), AUCTION.to_string(), token_id).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+}
+
+fn history(deps: &MockDeps, token_id: &str, start_after: Option<u64>) -> Vec<HistoryRecord> {
+    let res: HistoryResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::History {
+        token_id: token_id.to_string(),
+        start_after,
+        limit: None,
+    }).unwrap()).unwrap();
+    res.history
+}
+
+#[test]
+fn only_the_trusted_auction_records_sales() {
+    let (mut deps, token_id) = setup_auction_contract();
+
+    let sale = |recipient: &str| ExecuteMsg::Transfer {
+        recipient: recipient.to_string(),
+        token_id: token_id.clone(),
+        sale: Some(true),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), sale(AUCTION)).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info(AUCTION, &[]), sale(BOB)).unwrap();
+
+    let transfer_types: Vec<TransferType> = history(&deps, &token_id, None).into_iter().map(|record| record.transfer_type).collect();
+    assert_eq!(transfer_types, vec![TransferType::Mint, TransferType::Transfer, TransferType::Sale]);
+}
+
+#[test]
+fn untrusted_auctions_settle_sales_as_transfers() {
+    let mut deps = setup_contract(default_instantiate_msg());
+    let token_id = mint(&mut deps, ALICE);
+
+    // escrow and settlement as sent by an auction the collection does not trust
+    execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), ExecuteMsg::Transfer {
+        recipient: AUCTION.to_string(),
+        token_id: token_id.clone(),
+        sale: None,
+    }).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info(AUCTION, &[]), ExecuteMsg::Transfer {
+        recipient: BOB.to_string(),
+        token_id: token_id.clone(),
+        sale: Some(true),
+    }).unwrap();
+
+    assert_eq!(read_token_owner_store(&deps.storage, TokenId::new(token_id.clone())).unwrap(), Some(BOB.to_string()));
+    let transfer_types: Vec<TransferType> = history(&deps, &token_id, None).into_iter().map(|record| record.transfer_type).collect();
+    assert_eq!(transfer_types, vec![TransferType::Mint, TransferType::Transfer, TransferType::Transfer]);
+}
+
+#[test]
+fn history_pages_by_index() {
+    let (deps, token_id) = setup_auction_contract();
+
+    let records = history(&deps, &token_id, None);
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].owner, ALICE);
+    assert!(history(&deps, &token_id, Some(records[0].index)).is_empty());
+    assert!(history(&deps, &token_id, Some(u64::MAX)).is_empty());
+}
+
+#[test]
+fn burned_tokens_count_against_max_supply() {
+    let mut deps = setup_contract(InstantiateMsg { max_supply: Some(1), ..default_instantiate_msg() });
+    let token_id = mint(&mut deps, ALICE);
+
+    execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), ExecuteMsg::Burn { token_id }).unwrap();
+    assert_eq!(read_num_tokens_store(&deps.storage).unwrap(), 0);
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), ExecuteMsg::Mint(mint_msg(ALICE))).unwrap_err();
+    assert!(matches!(err, ContractError::MaxSupplyReached {}));
+    let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), ExecuteMsg::BatchMint { tokens: vec![mint_msg(ALICE)] }).unwrap_err();
+    assert!(matches!(err, ContractError::MaxSupplyReached {}));
+}
+
+#[test]
+fn hidden_collections_reveal_once() {
+    let hidden = InstantiateMsg { placeholder_uri: Some("ipfs://hidden".to_string()), ..default_instantiate_msg() };
+
+    let mut deps = mock_dependencies(&[]);
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), InstantiateMsg {
+        base_token_uri: Some("ipfs://final/".to_string()),
+        ..hidden.clone()
+    }).unwrap_err();
+    assert!(matches!(err, ContractError::RevealOnly {}));
+
+    let mut deps = setup_contract(hidden);
+    let token_id = mint(&mut deps, ALICE);
+    let res: NftInfoResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::NftInfo { token_id: token_id.clone() }).unwrap()).unwrap();
+    assert_eq!(res.token_uri, Some("ipfs://hidden".to_string()));
+
+    let reveal = ExecuteMsg::Reveal { base_uri: "ipfs://final/".to_string() };
+    execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), reveal.clone()).unwrap();
+    let res: NftInfoResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::NftInfo { token_id: token_id.clone() }).unwrap()).unwrap();
+    assert_eq!(res.token_uri, Some(format!("ipfs://final/{}", token_id)));
+    let res: ContractInfoResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::ContractInfo {}).unwrap()).unwrap();
+    assert_eq!(res.base_token_uri, Some("ipfs://final/".to_string()));
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), reveal).unwrap_err();
+    assert!(matches!(err, ContractError::AlreadyRevealed {}));
+}
+
+#[test]
+fn reveal_needs_a_placeholder() {
+    let mut deps = setup_contract(default_instantiate_msg());
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), ExecuteMsg::Reveal {
+        base_uri: "ipfs://final/".to_string(),
+    }).unwrap_err();
+    assert!(matches!(err, ContractError::NothingToReveal {}));
+}
+
+#[test]
+fn only_owners_and_operators_lock() {
+    let mut deps = setup_contract(default_instantiate_msg());
+    let token_id = mint(&mut deps, ALICE);
+    execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), ExecuteMsg::Approve {
+        recipient: BOB.to_string(),
+        token_id: token_id.clone(),
+        expires: None,
+    }).unwrap();
+
+    let lock = |unlock_authority: &str| ExecuteMsg::Lock {
+        token_id: token_id.clone(),
+        reason: "staked".to_string(),
+        unlock_authority: Some(unlock_authority.to_string()),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(BOB, &[]), lock(BOB)).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), lock(AUCTION)).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), mock_info(BOB, &[]), ExecuteMsg::TransferFrom {
+        sender: ALICE.to_string(),
+        recipient: BOB.to_string(),
+        token_id: token_id.clone(),
+    }).unwrap_err();
+    assert!(matches!(err, ContractError::TokenLocked {}));
+
+    let unlock = ExecuteMsg::Unlock { token_id: token_id.clone() };
+    let err = execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), unlock.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    execute(deps.as_mut(), mock_env(), mock_info(AUCTION, &[]), unlock).unwrap();
+    assert_eq!(read_token_lock_store(&deps.storage, TokenId::new(token_id)).unwrap(), None);
+}
+
+#[test]
+fn transfer_from_authorizes_the_caller() {
+    let mut deps = setup_contract(default_instantiate_msg());
+    let token_id = mint(&mut deps, ALICE);
+    let transfer_from = ExecuteMsg::TransferFrom {
+        sender: ALICE.to_string(),
+        recipient: BOB.to_string(),
+        token_id: token_id.clone(),
+    };
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info(BOB, &[]), transfer_from.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), ExecuteMsg::ApproveForAll {
+        opeartor: BOB.to_string(),
+        approved: true,
+        expires: None,
+    }).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info(BOB, &[]), transfer_from).unwrap();
+    assert_eq!(read_token_owner_store(&deps.storage, TokenId::new(token_id)).unwrap(), Some(BOB.to_string()));
+    assert_eq!(read_balance_store(&deps.storage, ALICE.to_string()).unwrap(), 0);
+    assert_eq!(read_balance_store(&deps.storage, BOB.to_string()).unwrap(), 1);
+}
+
+#[test]
+fn approvals_expire_and_are_revoked() {
+    let mut deps = setup_contract(default_instantiate_msg());
+    let token_id = mint(&mut deps, ALICE);
+    let approve = |expires: Expiration| ExecuteMsg::Approve {
+        recipient: BOB.to_string(),
+        token_id: token_id.clone(),
+        expires: Some(expires),
+    };
+
+    let err = execute(deps.as_mut(), env_at(100), mock_info(ALICE, &[]), approve(Expiration::AtHeight(100))).unwrap_err();
+    assert!(matches!(err, ContractError::Expired {}));
+
+    // an expired approval no longer allows the transfer
+    execute(deps.as_mut(), env_at(100), mock_info(ALICE, &[]), approve(Expiration::AtHeight(101))).unwrap();
+    let err = execute(deps.as_mut(), env_at(101), mock_info(BOB, &[]), ExecuteMsg::TransferNft {
+        recipient: BOB.to_string(),
+        token_id: token_id.clone(),
+    }).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    let revoke = ExecuteMsg::Revoke { spender: BOB.to_string(), token_id: token_id.clone() };
+    execute(deps.as_mut(), env_at(101), mock_info(ALICE, &[]), revoke.clone()).unwrap();
+    let err = execute(deps.as_mut(), env_at(101), mock_info(ALICE, &[]), revoke).unwrap_err();
+    assert!(matches!(err, ContractError::NotExistTokenAllowance {}));
+}
+
+#[test]
+fn transfers_clear_approvals() {
+    let mut deps = setup_contract(default_instantiate_msg());
+    let token_id = mint(&mut deps, ALICE);
+    for spender in &[BOB, AUCTION] {
+        execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), ExecuteMsg::Approve {
+            recipient: spender.to_string(),
+            token_id: token_id.clone(),
+            expires: None,
+        }).unwrap();
+    }
+    let res: OwnerOfResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::OwnerOf {
+        token_id: token_id.clone(),
+        include_expired: None,
+    }).unwrap()).unwrap();
+    assert_eq!(res.approvals.len(), 2);
+
+    execute(deps.as_mut(), mock_env(), mock_info(BOB, &[]), ExecuteMsg::TransferNft {
+        recipient: BOB.to_string(),
+        token_id: token_id.clone(),
+    }).unwrap();
+    let res: OwnerOfResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::OwnerOf {
+        token_id,
+        include_expired: Some(true),
+    }).unwrap()).unwrap();
+    assert_eq!(res, OwnerOfResponse { owner: BOB.to_string(), approvals: vec![] });
+}
+
+#[test]
+fn only_the_minter_mints() {
+    let mut deps = setup_contract(default_instantiate_msg());
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info(BOB, &[]), ExecuteMsg::Mint(mint_msg(BOB))).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), ExecuteMsg::TransferMinter { minter: BOB.to_string() }).unwrap();
+    let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), ExecuteMsg::Mint(mint_msg(BOB))).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    execute(deps.as_mut(), mock_env(), mock_info(BOB, &[]), ExecuteMsg::Mint(mint_msg(BOB))).unwrap();
+}
+
+#[test]
+fn cw721_queries_and_send() {
+    let mut deps = setup_contract(default_instantiate_msg());
+    let token_ids: Vec<String> = (0..3).map(|_| mint(&mut deps, ALICE)).collect();
+    mint(&mut deps, BOB);
+
+    let res: TokensResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Tokens {
+        owner: ALICE.to_string(),
+        start_after: None,
+        limit: Some(2),
+    }).unwrap()).unwrap();
+    assert_eq!(res.tokens, token_ids[..2].to_vec());
+    let res: TokensResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Tokens {
+        owner: ALICE.to_string(),
+        start_after: Some(token_ids[1].clone()),
+        limit: None,
+    }).unwrap()).unwrap();
+    assert_eq!(res.tokens, token_ids[2..].to_vec());
+    let res: TokensResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::AllTokens {
+        start_after: None,
+        limit: None,
+    }).unwrap()).unwrap();
+    assert_eq!(res.tokens.len(), 4);
+
+    let res = execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), ExecuteMsg::SendNft {
+        contract: AUCTION.to_string(),
+        token_id: token_ids[0].clone(),
+        msg: Binary::from(b"{}".to_vec()),
+    }).unwrap();
+    assert_eq!(res.messages.len(), 1);
+    assert_eq!(read_token_owner_store(&deps.storage, TokenId::new(token_ids[0].clone())).unwrap(), Some(AUCTION.to_string()));
+}
+
+#[test]
+fn frozen_metadata_cannot_be_updated() {
+    let mut deps = setup_contract(default_instantiate_msg());
+    let token_id = mint(&mut deps, ALICE);
+    let update = |freeze_metadata: bool| ExecuteMsg::UpdateMetadata {
+        token_id: token_id.clone(),
+        extension: Some(Metadata {
+            description: Some("updated".to_string()),
+            image: None,
+            external_url: None,
+            attributes: None,
+            animation_url: None,
+        }),
+        freeze_metadata: Some(freeze_metadata),
+    };
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), update(false)).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), update(true)).unwrap();
+    let res: NftInfoResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::NftInfo { token_id: token_id.clone() }).unwrap()).unwrap();
+    assert_eq!(res.extension.unwrap().description, Some("updated".to_string()));
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), update(false)).unwrap_err();
+    assert!(matches!(err, ContractError::MetadataFrozen {}));
+}
+
+#[test]
+fn sequential_ids_are_not_reused_after_burn() {
+    let mut deps = setup_contract(default_instantiate_msg());
+    let first = mint(&mut deps, ALICE);
+    execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), ExecuteMsg::Burn { token_id: first.clone() }).unwrap();
+
+    let second = mint(&mut deps, ALICE);
+    assert_ne!(first, second);
+    let res: NumTokensResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::NumTokens {}).unwrap()).unwrap();
+    assert_eq!(res.count, 1);
+}
+
+#[test]
+fn custom_token_ids() {
+    let mut deps = setup_contract(InstantiateMsg { token_id_mode: Some(TokenIdMode::Custom), ..default_instantiate_msg() });
+    let mint_custom = |token_id: Option<&str>| ExecuteMsg::Mint(MintMsg {
+        token_id: token_id.map(|v| v.to_string()),
+        ..mint_msg(ALICE)
+    });
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), mint_custom(None)).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidTokenId {}));
+    let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), mint_custom(Some("genesis 001"))).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidTokenId {}));
+
+    execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), mint_custom(Some("genesis-001"))).unwrap();
+    assert_eq!(read_token_owner_store(&deps.storage, TokenId::new("genesis-001".to_string())).unwrap(), Some(ALICE.to_string()));
+    let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), mint_custom(Some("genesis-001"))).unwrap_err();
+    assert!(matches!(err, ContractError::TokenAlreadyExists {}));
+
+    // sequential collections assign the id themselves
+    let mut deps = setup_contract(default_instantiate_msg());
+    let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), mint_custom(Some("genesis-001"))).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidTokenId {}));
+}
+
+#[test]
+fn is_approved_for_all_honors_expiration() {
+    let mut deps = setup_contract(default_instantiate_msg());
+    execute(deps.as_mut(), env_at(100), mock_info(ALICE, &[]), ExecuteMsg::ApproveAll {
+        operator: BOB.to_string(),
+        expires: Some(Expiration::AtHeight(200)),
+    }).unwrap();
+
+    let approved = |height: u64| {
+        let res: IsApprovedForAllResponse = from_binary(&query(deps.as_ref(), env_at(height), QueryMsg::IsApprovedForAll {
+            owner: ALICE.to_string(),
+            operator: BOB.to_string(),
+        }).unwrap()).unwrap();
+        res.approved
+    };
+    assert!(approved(199));
+    assert!(!approved(200));
+}
+
+#[test]
+fn token_royalty_overrides_the_collection_royalty() {
+    let royalty = |payment_address: &str, royalty_bps: u64| Royalty { payment_address: payment_address.to_string(), royalty_bps };
+
+    let mut deps = mock_dependencies(&[]);
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), InstantiateMsg {
+        royalty: Some(royalty(CREATOR, 10_001)),
+        ..default_instantiate_msg()
+    }).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidRoyalty {}));
+
+    let mut deps = setup_contract(InstantiateMsg { royalty: Some(royalty(CREATOR, 500)), ..default_instantiate_msg() });
+    let default_token = mint(&mut deps, ALICE);
+    let res = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), ExecuteMsg::Mint(MintMsg {
+        royalty: Some(royalty(BOB, 1_000)),
+        ..mint_msg(ALICE)
+    })).unwrap();
+    let own_token = res.attributes.iter().find(|attr| attr.key == "token_id").unwrap().value.clone();
+
+    let royalty_info = |token_id: &str| {
+        let res: RoyaltyInfoResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::RoyaltyInfo {
+            token_id: token_id.to_string(),
+            sale_price: Uint128(1_000),
+        }).unwrap()).unwrap();
+        res
+    };
+    assert_eq!(royalty_info(&default_token), RoyaltyInfoResponse { address: CREATOR.to_string(), royalty_amount: Uint128(50) });
+    assert_eq!(royalty_info(&own_token), RoyaltyInfoResponse { address: BOB.to_string(), royalty_amount: Uint128(100) });
+}
+
+#[test]
+fn batch_mint_and_transfer() {
+    let mut deps = setup_contract(default_instantiate_msg());
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), ExecuteMsg::BatchMint { tokens: vec![] }).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidBatchSize {}));
+    let tokens = vec![mint_msg(ALICE); MAX_BATCH_SIZE + 1];
+    let err = execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), ExecuteMsg::BatchMint { tokens }).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidBatchSize {}));
+
+    execute(deps.as_mut(), mock_env(), mock_info(CREATOR, &[]), ExecuteMsg::BatchMint {
+        tokens: vec![mint_msg(ALICE), mint_msg(ALICE), mint_msg(BOB)],
+    }).unwrap();
+    assert_eq!(read_balance_store(&deps.storage, ALICE.to_string()).unwrap(), 2);
+    assert_eq!(read_balance_store(&deps.storage, BOB.to_string()).unwrap(), 1);
+    assert_eq!(read_num_tokens_store(&deps.storage).unwrap(), 3);
+
+    let res: TokensResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Tokens {
+        owner: ALICE.to_string(),
+        start_after: None,
+        limit: None,
+    }).unwrap()).unwrap();
+    execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), ExecuteMsg::BatchTransfer {
+        recipient: BOB.to_string(),
+        token_ids: res.tokens.clone(),
+    }).unwrap();
+    assert_eq!(read_balance_store(&deps.storage, ALICE.to_string()).unwrap(), 0);
+    assert_eq!(read_balance_store(&deps.storage, BOB.to_string()).unwrap(), 3);
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info(BOB, &[]), ExecuteMsg::BatchTransfer {
+        recipient: ALICE.to_string(),
+        token_ids: vec![res.tokens[0].clone(), res.tokens[0].clone()],
+    }).unwrap_err();
+    assert!(matches!(err, ContractError::DuplicateTokenId {}));
+}
+
+#[test]
+fn soulbound_tokens_only_burn() {
+    let mut deps = setup_contract(InstantiateMsg { transferable: Some(false), ..default_instantiate_msg() });
+    let token_id = mint(&mut deps, ALICE);
+
+    let res: TransferableResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::Transferable { token_id: token_id.clone() }).unwrap()).unwrap();
+    assert!(!res.transferable);
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), ExecuteMsg::TransferNft {
+        recipient: BOB.to_string(),
+        token_id: token_id.clone(),
+    }).unwrap_err();
+    assert!(matches!(err, ContractError::NonTransferable {}));
+    let err = execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), ExecuteMsg::Approve {
+        recipient: BOB.to_string(),
+        token_id: token_id.clone(),
+        expires: None,
+    }).unwrap_err();
+    assert!(matches!(err, ContractError::NonTransferable {}));
+
+    execute(deps.as_mut(), mock_env(), mock_info(ALICE, &[]), ExecuteMsg::Burn { token_id: token_id.clone() }).unwrap();
+    assert_eq!(read_token_owner_store(&deps.storage, TokenId::new(token_id)).unwrap(), None);
+}
+
+#[test]
+fn snapshots_answer_past_owners_and_balances() {
+    let mut deps = setup_contract(InstantiateMsg { snapshots: Some(true), ..default_instantiate_msg() });
+    let res = execute(deps.as_mut(), env_at(100), mock_info(CREATOR, &[]), ExecuteMsg::Mint(mint_msg(ALICE))).unwrap();
+    let token_id = res.attributes.iter().find(|attr| attr.key == "token_id").unwrap().value.clone();
+    execute(deps.as_mut(), env_at(200), mock_info(ALICE, &[]), ExecuteMsg::TransferNft {
+        recipient: BOB.to_string(),
+        token_id: token_id.clone(),
+    }).unwrap();
+
+    let owner_at = |height: u64| {
+        let res: OwnerAtResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::OwnerAt {
+            token_id: token_id.clone(),
+            height,
+        }).unwrap()).unwrap();
+        res.owner
+    };
+    assert_eq!(owner_at(99), None);
+    assert_eq!(owner_at(100), Some(ALICE.to_string()));
+    assert_eq!(owner_at(199), Some(ALICE.to_string()));
+    assert_eq!(owner_at(200), Some(BOB.to_string()));
+
+    let balance_at = |address: &str, height: u64| {
+        let res: BalanceAtResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::BalanceAt {
+            address: address.to_string(),
+            height,
+        }).unwrap()).unwrap();
+        res.balance
+    };
+    assert_eq!(balance_at(ALICE, 150), 1);
+    assert_eq!(balance_at(ALICE, 200), 0);
+    assert_eq!(balance_at(BOB, 150), 0);
+    assert_eq!(balance_at(BOB, 200), 1);
+
+    // collections without snapshots refuse the queries
+    let deps = setup_contract(default_instantiate_msg());
+    assert!(query(deps.as_ref(), mock_env(), QueryMsg::BalanceAt { address: ALICE.to_string(), height: 1 }).is_err());
+}
+
+#[test]
+fn users_expire_and_are_cleared_on_transfer() {
+    let mut deps = setup_contract(default_instantiate_msg());
+    let token_id = mint(&mut deps, ALICE);
+    let set_user = |expires: Expiration| ExecuteMsg::SetUser {
+        token_id: token_id.clone(),
+        user: Some(BOB.to_string()),
+        expires,
+    };
+
+    let err = execute(deps.as_mut(), env_at(100), mock_info(BOB, &[]), set_user(Expiration::AtHeight(200))).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let err = execute(deps.as_mut(), env_at(100), mock_info(ALICE, &[]), set_user(Expiration::AtHeight(100))).unwrap_err();
+    assert!(matches!(err, ContractError::Expired {}));
+    execute(deps.as_mut(), env_at(100), mock_info(ALICE, &[]), set_user(Expiration::AtHeight(200))).unwrap();
+
+    let user_of = |deps: &MockDeps, height: u64| {
+        let res: UserOfResponse = from_binary(&query(deps.as_ref(), env_at(height), QueryMsg::UserOf { token_id: token_id.clone() }).unwrap()).unwrap();
+        res.user
+    };
+    assert_eq!(user_of(&deps, 199), Some(BOB.to_string()));
+    assert_eq!(user_of(&deps, 200), None);
+
+    execute(deps.as_mut(), env_at(150), mock_info(ALICE, &[]), ExecuteMsg::TransferNft {
+        recipient: AUCTION.to_string(),
+        token_id: token_id.clone(),
+    }).unwrap();
+    assert_eq!(user_of(&deps, 150), None);
+}
+
+#[test]
+fn permits_check_nonce_and_signature() {
+    let mut deps = setup_contract(default_instantiate_msg());
+    let token_id = mint(&mut deps, ALICE);
+    let permit = |token_id: &str, nonce: u64| ExecuteMsg::Permit(PermitMsg {
+        spender: BOB.to_string(),
+        token_id: token_id.to_string(),
+        nonce,
+        expires: None,
+        pubkey: Binary::from(vec![2u8; 33]),
+        signature: Binary::from(vec![1u8; 64]),
+    });
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info(BOB, &[]), permit("404", 0)).unwrap_err();
+    assert!(matches!(err, ContractError::NotExistToken {}));
+    let err = execute(deps.as_mut(), mock_env(), mock_info(BOB, &[]), permit(&token_id, 1)).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidNonce {}));
+    let err = execute(deps.as_mut(), mock_env(), mock_info(BOB, &[]), permit(&token_id, 0)).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidSignature {}));
+
+    // a rejected permit neither approves nor uses up the nonce
+    let res: PermitNonceResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PermitNonce { owner: ALICE.to_string() }).unwrap()).unwrap();
+    assert_eq!(res.nonce, 0);
+    assert_eq!(read_token_approval_store(&deps.storage, TokenId::new(token_id), BOB.to_string()).unwrap(), None);
+}
+
+#[test]
+fn permit_signer_is_the_address_of_the_key() {
+    let deps = mock_dependencies(&[]);
+    let key = SigningKey::new(&[7u8; 32]).unwrap();
+    let pubkey = key.verify_key().to_bytes();
+    let message = b"permit payload";
+    let signature: Signature = key.sign(message);
+    let hash = Sha256::digest(message);
+
+    // ripemd160(sha256(pubkey)) of the compressed key 02989c0b..5f6f
+    let signer = permit_signer(&deps.api, hash.as_slice(), signature.as_ref(), pubkey.as_slice()).unwrap();
+    assert_eq!(signer, vec![
+        163, 198, 177, 238, 74, 73, 217, 242, 175, 59, 56, 2, 151, 71, 68, 251, 169, 36, 22, 74
+    ]);
+
+    let other = Sha256::digest(b"another payload");
+    let err = permit_signer(&deps.api, other.as_slice(), signature.as_ref(), pubkey.as_slice()).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidSignature {}));
+}
+
+#[test]
+fn applied_permits_cannot_be_replayed() {
+    let mut deps = setup_contract(default_instantiate_msg());
+    let token_id = mint(&mut deps, ALICE);
+    let msg = PermitMsg {
+        spender: BOB.to_string(),
+        token_id: token_id.clone(),
+        nonce: 0,
+        expires: None,
+        pubkey: Binary::from(vec![2u8; 33]),
+        signature: Binary::from(vec![1u8; 64]),
+    };
+
+    let res = apply_permit(deps.as_mut(), mock_env(), ALICE.to_string(), msg.clone()).unwrap();
+    assert!(res.attributes.iter().any(|a| a.key == "permit_nonce" && a.value == "0"));
+    let res: PermitNonceResponse = from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::PermitNonce { owner: ALICE.to_string() }).unwrap()).unwrap();
+    assert_eq!(res.nonce, 1);
+    assert!(read_token_approval_store(&deps.storage, TokenId::new(token_id), BOB.to_string()).unwrap().is_some());
+
+    let err = execute(deps.as_mut(), mock_env(), mock_info(BOB, &[]), ExecuteMsg::Permit(msg)).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidNonce {}));
+}